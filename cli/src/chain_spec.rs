@@ -22,7 +22,7 @@ use serde::{Serialize, Deserialize};
 use node_runtime::{
     AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, ContractsConfig, CouncilConfig, DemocracyConfig,
     GrandpaConfig, ImOnlineConfig, IndicesConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig, ElectionsPhragmenConfig, SudoConfig,
-    SystemConfig, TechnicalCommitteeConfig, DidConfig, AdsConfig, WASM_BINARY,
+    SystemConfig, TechnicalCommitteeConfig, DidConfig, AdsConfig, OracleConfig, PricesConfig, WASM_BINARY,
 };
 use node_runtime::Block;
 use node_runtime::constants::currency::*;
@@ -182,6 +182,9 @@ pub fn testnet_genesis(
     const ENDOWMENT: Balance = 70_000_000 * DOLLARS;
     const STASH: Balance = 100_000 * DOLLARS;
 
+    let did_genesis_account: AccountId = hex!["22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f"].into();
+    let ads_contract: AccountId = hex!["0c98c49f1861d5f6ed9ea27230796a76878abbfbfb9716c64b2c7479a2197435"].into();
+
     GenesisConfig {
         frame_system: Some(SystemConfig {
             code: WASM_BINARY.to_vec(),
@@ -256,16 +259,34 @@ pub fn testnet_genesis(
         pallet_membership_Instance1: Some(Default::default()),
         pallet_treasury: Some(Default::default()),
         did: Some(DidConfig {
-            genesis_account: hex!["22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f"].into(),
+            genesis_account: did_genesis_account.clone(),
             min_deposit: 10 * DOLLARS,
             base_quota: 250,
             fee_to_previous: 25 * DOLLARS,
+            dids: genesis_dids(&did_genesis_account, &ads_contract),
         }),
         ads: Some(AdsConfig {
-            contract: hex!["0c98c49f1861d5f6ed9ea27230796a76878abbfbfb9716c64b2c7479a2197435"].into(),
+            contract: ads_contract,
             min_deposit: 500 * DOLLARS,
         }),
+        oracle: Some(OracleConfig {
+            authorities: initial_authorities.iter().map(|x| x.0.clone()).collect(),
+            threshold: initial_authorities.len() as u32 / 2 + 1,
+        }),
+        prices: Some(PricesConfig {
+            authorities: initial_authorities.first().map(|x| x.0.clone()),
+        }),
+    }
+}
+
+/// Dids seeded at genesis on test chains, so the ads `Contract` account can
+/// receive and pay out ad budgets from the first block.
+pub fn genesis_dids(genesis_account: &AccountId, contract: &AccountId) -> Vec<(AccountId, Vec<u8>, Vec<u8>)> {
+    let mut dids = vec![(genesis_account.clone(), AsRef::<[u8]>::as_ref(genesis_account).to_vec(), b"1".to_vec())];
+    if contract != genesis_account {
+        dids.push((contract.clone(), AsRef::<[u8]>::as_ref(contract).to_vec(), b"1".to_vec()));
     }
+    dids
 }
 
 fn development_config_genesis() -> GenesisConfig {
@@ -420,6 +441,7 @@ fn prochain_genesis(
             min_deposit: 10 * DOLLARS,
             base_quota: 250,
             fee_to_previous: 25 * DOLLARS,
+            dids: vec![],
         }),
         ads: Some(AdsConfig {
             contract: hex!["22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f"].into(),
@@ -631,6 +653,20 @@ pub(crate) mod tests {
         local_testnet_config().build_storage().unwrap();
     }
 
+    #[test]
+    fn test_genesis_seeds_contract_did() {
+        let genesis_account: AccountId = hex!["22df4b685df33f070ae6e5ee27f745de078adff099d3a803ec67afe1168acd4f"].into();
+        let contract: AccountId = hex!["0c98c49f1861d5f6ed9ea27230796a76878abbfbfb9716c64b2c7479a2197435"].into();
+        assert_eq!(genesis_dids(&genesis_account, &contract).len(), 2);
+        assert_eq!(genesis_dids(&genesis_account, &genesis_account).len(), 1);
+
+        let storage = local_testnet_config().build_storage().unwrap();
+        sp_io::TestExternalities::new(storage).execute_with(|| {
+            assert!(node_runtime::Did::identity(&contract).is_some());
+            assert_eq!(node_runtime::Ads::contract(), contract);
+        });
+    }
+
     /// Local testnet config (single validator - Alice)
     pub fn integration_test_config_with_single_authority() -> ChainSpec {
         ChainSpec::from_genesis(
@@ -16,6 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use std::path::PathBuf;
 use sc_cli::RunCmd;
 use structopt::StructOpt;
 
@@ -28,6 +29,10 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+	/// JSON file with the endpoints and API keys of the offchain workers, written to offchain
+	/// local storage on startup.
+	#[structopt(long = "offchain-source", value_name = "PATH", parse(from_os_str))]
+	pub offchain_source: Option<PathBuf>,
 }
 
 /// Possible subcommands of the main binary.
@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{chain_spec, service, Cli, Subcommand};
+use crate::{chain_spec, offchain_source::OffchainSources, service, Cli, Subcommand};
 use node_executor::Executor;
 use node_runtime::{Block, RuntimeApi};
 use sc_cli::{Result, SubstrateCli};
@@ -70,10 +70,14 @@ pub fn run() -> Result<()> {
 
 	match &cli.subcommand {
 		None => {
+			let offchain_source = cli.offchain_source.as_ref()
+				.map(|path| OffchainSources::from_file(path))
+				.transpose()
+				.map_err(sc_cli::Error::Input)?;
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node(
 				service::new_light,
-				service::new_full,
+				|config| service::new_full_with_offchain_source(config, offchain_source),
 				node_runtime::VERSION
 			)
 		}
@@ -34,6 +34,7 @@ pub mod chain_spec;
 
 #[macro_use]
 mod service;
+mod offchain_source;
 #[cfg(feature = "browser")]
 mod browser;
 #[cfg(feature = "cli")]
@@ -0,0 +1,135 @@
+//! Offchain worker sources loaded with `--offchain-source`.
+//!
+//! The file holds the endpoints and API keys used by the oracle and prices offchain workers.
+//! They are written to the node's offchain local storage on startup, where the runtime reads
+//! them, so secrets never reach the chain. Example:
+//!
+//! ```json
+//! {
+//!   "oracle": { "url": "https://api.etherscan.io/api?apikey=KEY" },
+//!   "prices": [
+//!     {
+//!       "symbol": "PRM",
+//!       "source": "coinmarketcap",
+//!       "url": "https://pro-api.coinmarketcap.com/v1/cryptocurrency/quotes/latest?id=2275",
+//!       "headers": { "X-CMC_PRO_API_KEY": "KEY" }
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::{collections::BTreeMap, fs, path::Path};
+use codec::Encode;
+use serde::Deserialize;
+use sp_core::offchain::{OffchainStorage, STORAGE_PREFIX};
+use prices::PriceSource;
+use utilities::http::Endpoint;
+
+/// An url and the headers sent to it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EndpointConfig {
+	pub url: String,
+	#[serde(default)]
+	pub headers: BTreeMap<String, String>,
+}
+
+fn endpoint(url: &str, headers: &BTreeMap<String, String>) -> Endpoint {
+	Endpoint {
+		url: url.as_bytes().to_vec(),
+		headers: headers.iter()
+			.map(|(name, value)| (name.as_bytes().to_vec(), value.as_bytes().to_vec()))
+			.collect(),
+	}
+}
+
+/// A price fetched by the prices offchain worker.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriceSourceConfig {
+	pub symbol: String,
+	pub source: String,
+	pub url: String,
+	#[serde(default)]
+	pub headers: BTreeMap<String, String>,
+}
+
+/// Sources of the offchain workers.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OffchainSources {
+	/// Replaces the event url set on chain by `oracle::kickoff`.
+	pub oracle: Option<EndpointConfig>,
+	#[serde(default)]
+	pub prices: Vec<PriceSourceConfig>,
+}
+
+impl OffchainSources {
+	/// Read the sources from a JSON file.
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let json = fs::read(path)
+			.map_err(|e| format!("Error reading offchain source {}: {}", path.display(), e))?;
+		serde_json::from_slice(&json)
+			.map_err(|e| format!("Error parsing offchain source {}: {}", path.display(), e))
+	}
+
+	/// Write the sources to offchain local storage, replacing earlier ones.
+	pub fn write<S: OffchainStorage>(&self, storage: &mut S) {
+		if let Some(oracle) = &self.oracle {
+			storage.set(STORAGE_PREFIX, oracle::ENDPOINT_KEY, &endpoint(&oracle.url, &oracle.headers).encode());
+		}
+		if !self.prices.is_empty() {
+			let sources: Vec<PriceSource> = self.prices.iter()
+				.map(|price| PriceSource {
+					symbol: price.symbol.as_bytes().to_vec(),
+					source: price.source.as_bytes().to_vec(),
+					endpoint: endpoint(&price.url, &price.headers),
+				})
+				.collect();
+			storage.set(STORAGE_PREFIX, prices::SOURCES_KEY, &sources.encode());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use sp_core::offchain::storage::InMemOffchainStorage;
+
+	#[test]
+	fn sources_should_be_written_to_local_storage() {
+		let sources: OffchainSources = serde_json::from_str(r#"{
+			"oracle": { "url": "https://api.etherscan.io/api?apikey=KEY" },
+			"prices": [
+				{ "symbol": "BTC", "source": "coincap", "url": "https://api.coincap.io/v2/assets/bitcoin" },
+				{ "symbol": "PRM", "source": "coinmarketcap", "url": "https://cmc/quotes", "headers": { "X-CMC_PRO_API_KEY": "KEY" } }
+			]
+		}"#).unwrap();
+		let mut storage = InMemOffchainStorage::default();
+		sources.write(&mut storage);
+
+		let oracle = storage.get(STORAGE_PREFIX, oracle::ENDPOINT_KEY).unwrap();
+		assert_eq!(Endpoint::decode(&mut &oracle[..]).unwrap(), Endpoint {
+			url: b"https://api.etherscan.io/api?apikey=KEY".to_vec(),
+			headers: vec![],
+		});
+
+		let sources = storage.get(STORAGE_PREFIX, prices::SOURCES_KEY).unwrap();
+		let sources = Vec::<PriceSource>::decode(&mut &sources[..]).unwrap();
+		assert_eq!(sources.len(), 2);
+		assert_eq!(sources[1], PriceSource {
+			symbol: b"PRM".to_vec(),
+			source: b"coinmarketcap".to_vec(),
+			endpoint: Endpoint {
+				url: b"https://cmc/quotes".to_vec(),
+				headers: vec![(b"X-CMC_PRO_API_KEY".to_vec(), b"KEY".to_vec())],
+			},
+		});
+	}
+
+	#[test]
+	fn unknown_fields_should_be_rejected() {
+		assert!(serde_json::from_str::<OffchainSources>(r#"{ "oracle": { "uri": "x" } }"#).is_err());
+	}
+}
@@ -33,6 +33,7 @@ use sc_service::{
 };
 use sp_inherents::InherentDataProviders;
 use sc_consensus::LongestChain;
+use crate::offchain_source::OffchainSources;
 
 /// Starts a `ServiceBuilder` for a full service.
 ///
@@ -152,10 +153,10 @@ macro_rules! new_full_start {
 /// We need to use a macro because the test suit doesn't work with an opaque service. It expects
 /// concrete types instead.
 macro_rules! new_full {
-	($config:expr, $with_startup_data: expr) => {{
+	($config:expr, $with_startup_data: expr, $offchain_source: expr) => {{
 		use futures::prelude::*;
 		use sc_network::Event;
-		use sc_client_api::ExecutorProvider;
+		use sc_client_api::{Backend, ExecutorProvider};
 		use sp_core::traits::BareCryptoStorePtr;
 
 		let (
@@ -173,6 +174,13 @@ macro_rules! new_full {
 		let (builder, mut import_setup, inherent_data_providers, mut rpc_setup) =
 			new_full_start!($config);
 
+		let offchain_source: Option<crate::offchain_source::OffchainSources> = $offchain_source;
+		if let Some(sources) = offchain_source {
+			let mut storage = builder.backend().offchain_storage()
+				.ok_or_else(|| sc_service::Error::Other("Offchain storage is not available".into()))?;
+			sources.write(&mut storage);
+		}
+
 		let service = builder
 			.with_finality_proof_provider(|client, backend| {
 				// GenesisAuthoritySetProvider is implemented for StorageAndProofProvider
@@ -306,8 +314,11 @@ macro_rules! new_full {
 
 		Ok((service, inherent_data_providers))
 	}};
+	($config:expr, $with_startup_data: expr) => {{
+		new_full!($config, $with_startup_data, None)
+	}};
 	($config:expr) => {{
-		new_full!($config, |_, _| {})
+		new_full!($config, |_, _| {}, None)
 	}}
 }
 
@@ -318,6 +329,14 @@ pub fn new_full(config: Configuration)
 	new_full!(config).map(|(service, _)| service)
 }
 
+/// Builds a new service for a full client, writing `offchain_source` to its offchain local
+/// storage first.
+pub fn new_full_with_offchain_source(config: Configuration, offchain_source: Option<OffchainSources>)
+	-> Result<impl AbstractService, ServiceError>
+{
+	new_full!(config, |_, _| {}, offchain_source).map(|(service, _)| service)
+}
+
 /// Builds a new service for a light client.
 pub fn new_light(config: Configuration)
 				 -> Result<impl AbstractService, ServiceError> {
@@ -0,0 +1,187 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2018-2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sp_core::NeverNativeValue;
+use sp_runtime::Permill;
+use frame_support::traits::Get;
+use frame_system::{EventRecord, Phase};
+
+use node_runtime::{
+	Call, CheckedExtrinsic, Runtime, Balances, System, Event, Did, Ads,
+	constants::currency::*,
+};
+use node_primitives::{Balance, Hash};
+use node_testing::keyring::*;
+
+pub mod common;
+use self::common::*;
+
+const PUBLISH_AMOUNT: Balance = 50 * DOLLARS;
+const CLICK_FEE: Balance = 1 * DOLLARS;
+const WITHDRAW_AMOUNT: Balance = 10 * DOLLARS;
+const BLOCK1_TIMESTAMP: u64 = 42 * 1000;
+/// ad budgets are locked for 30 days after publishing
+const BLOCK2_TIMESTAMP: u64 = BLOCK1_TIMESTAMP + 30 * 24 * 60 * 60 * 1000;
+
+fn did_key(t: &mut TestExternalities<sp_runtime::traits::BlakeTwo256>, who: &node_primitives::AccountId) -> Hash {
+	t.execute_with(|| Did::identity(who).expect("did is seeded at genesis").0)
+}
+
+/// Block 1 creates dave's did, publishes and approves an ad distributed by bob.
+/// Block 2 pays dave for a click and lets charlie withdraw part of the budget.
+fn ads_blocks() -> ((Vec<u8>, Hash), (Vec<u8>, Hash)) {
+	let mut t = new_test_ext_with_dids(COMPACT_CODE);
+	let alice_key = did_key(&mut t, &alice());
+
+	let block1 = construct_block(
+		&mut t,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(BLOCK1_TIMESTAMP)),
+			},
+			CheckedExtrinsic {
+				signed: Some((alice(), signed_extra(0, 0))),
+				function: Call::Did(did::Call::create(
+					AsRef::<[u8]>::as_ref(&dave()).to_vec(),
+					dave(),
+					b"1".to_vec(),
+					alice_key,
+					None,
+					None,
+				)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(0, 0))),
+				function: Call::Ads(ads::Call::publish(
+					b"prochain".to_vec(),
+					b"did".to_vec(),
+					PUBLISH_AMOUNT,
+					CLICK_FEE,
+					b"https://prochain.network/ad.png".to_vec(),
+					None,
+					ads::DistributeType::AGENT,
+					Default::default(),
+				)),
+			},
+			CheckedExtrinsic {
+				signed: Some((bob(), signed_extra(0, 0))),
+				function: Call::Ads(ads::Call::register_agent(Permill::zero())),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(1, 0))),
+				function: Call::Ads(ads::Call::assign_agent(0, bob())),
+			},
+			CheckedExtrinsic {
+				signed: Some((alice(), signed_extra(1, 0))),
+				function: Call::Sudo(pallet_sudo::Call::sudo(Box::new(Call::Ads(ads::Call::approve(0))))),
+			},
+		]
+	);
+
+	let dave_key = did_key(&mut t, &dave());
+	let block2 = construct_block(
+		&mut t,
+		2,
+		block1.1.clone(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(BLOCK2_TIMESTAMP)),
+			},
+			CheckedExtrinsic {
+				signed: Some((bob(), signed_extra(1, 0))),
+				function: Call::Ads(ads::Call::distribute(0, dave_key)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(2, 0))),
+				function: Call::Ads(ads::Call::withdraw(0, WITHDRAW_AMOUNT, b"withdraw".to_vec())),
+			},
+		]
+	);
+
+	(block1, block2)
+}
+
+fn import_ads_blocks(use_native: bool) {
+	let mut t = new_test_ext_with_dids(COMPACT_CODE);
+	let (block1, block2) = ads_blocks();
+	let dave_before = t.execute_with(|| Balances::free_balance(&dave()));
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&block1.0,
+		use_native,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		let charlie_key = Did::identity(&charlie()).unwrap().0;
+		assert!(Did::identity(&dave()).is_some());
+		assert_eq!(Ads::owned_ads(charlie_key), vec![0]);
+		assert_eq!(Ads::remaining_budget(0), Some(PUBLISH_AMOUNT));
+		assert_eq!(Ads::active_ads(0, 10), vec![0]);
+		assert!(Ads::agents(&bob()).is_some());
+		assert_eq!(Balances::reserved_balance(&bob()), <Runtime as ads::Trait>::AgentStake::get());
+		assert!(System::events().iter().any(|r| r.event == Event::ads(ads::RawEvent::Approved(0))));
+	});
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&block2.0,
+		use_native,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		let bob_key = Did::identity(&bob()).unwrap().0;
+		let charlie_key = Did::identity(&charlie()).unwrap().0;
+		let dave_key = Did::identity(&dave()).unwrap().0;
+
+		// dave did not sign anything, so the click reward is all that changed
+		assert_eq!(Balances::free_balance(&dave()), dave_before + CLICK_FEE);
+		assert_eq!(Ads::rewards_paid(0), CLICK_FEE);
+		assert_eq!(Ads::ad_user_rewards(0, dave_key), 1);
+		assert_eq!(Ads::remaining_budget(0), Some(PUBLISH_AMOUNT - WITHDRAW_AMOUNT - CLICK_FEE));
+
+		let events = System::events();
+		assert!(events.contains(&EventRecord {
+			phase: Phase::ApplyExtrinsic(1),
+			event: Event::ads(ads::RawEvent::Distributed(bob_key, dave_key, CLICK_FEE)),
+			topics: vec![],
+		}));
+		assert!(events.contains(&EventRecord {
+			phase: Phase::ApplyExtrinsic(2),
+			event: Event::ads(ads::RawEvent::Withdraw(charlie_key, WITHDRAW_AMOUNT)),
+			topics: vec![],
+		}));
+	});
+}
+
+#[test]
+fn full_native_ads_block_import_works() {
+	import_ads_blocks(true);
+}
+
+#[test]
+fn full_wasm_ads_block_import_works() {
+	import_ads_blocks(false);
+}
@@ -37,9 +37,9 @@ use sc_executor::error::Result;
 use node_executor::Executor;
 use node_runtime::{
 	Header, Block, UncheckedExtrinsic, CheckedExtrinsic, Runtime, BuildStorage,
-	constants::currency::*,
+	DidConfig, AdsConfig, constants::currency::*,
 };
-use node_primitives::{Hash, BlockNumber};
+use node_primitives::{AccountId, Hash, BlockNumber};
 use node_testing::keyring::*;
 use sp_externalities::Externalities;
 
@@ -133,6 +133,36 @@ pub fn new_test_ext(code: &[u8], support_changes_trie: bool) -> TestExternalitie
 	ext
 }
 
+fn did_seed(who: AccountId, did_type: &[u8]) -> (AccountId, Vec<u8>, Vec<u8>) {
+	let pubkey = AsRef::<[u8]>::as_ref(&who).to_vec();
+	(who, pubkey, did_type.to_vec())
+}
+
+/// Same as `new_test_ext`, with alice as did genesis account, bob as the ads
+/// `Contract` and charlie as an advertiser, all seeded as dids.
+pub fn new_test_ext_with_dids(code: &[u8]) -> TestExternalities<BlakeTwo256> {
+	let mut storage = node_testing::genesis::config(false, Some(code)).build_storage().unwrap();
+	DidConfig {
+		genesis_account: alice(),
+		min_deposit: 10 * DOLLARS,
+		base_quota: 250,
+		fee_to_previous: 25 * DOLLARS,
+		dids: vec![
+			did_seed(alice(), b"1"),
+			did_seed(bob(), b"1"),
+			did_seed(charlie(), b"4"),
+		],
+	}.assimilate_storage(&mut storage).unwrap();
+	AdsConfig {
+		contract: bob(),
+		min_deposit: 10 * DOLLARS,
+	}.assimilate_storage(&mut storage).unwrap();
+
+	let mut ext = TestExternalities::new_with_code(code, storage);
+	ext.changes_trie_storage().insert(0, GENESIS_HASH.into(), Default::default());
+	ext
+}
+
 /// Construct a fake block.
 ///
 /// `extrinsics` must be a list of valid extrinsics, i.e. none of the extrinsics for example
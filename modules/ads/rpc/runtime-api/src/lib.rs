@@ -0,0 +1,35 @@
+//! Runtime API definition for the ads module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+pub use ads::{AdIndex, CampaignStats, TargetingQuery};
+
+sp_api::decl_runtime_apis! {
+	/// Version 2 added `slot_winners`.
+	#[api_version(2)]
+	pub trait AdsApi<Hash, Balance> where
+		Hash: Codec,
+		Balance: Codec,
+	{
+		/// Ads owned by the given did hash.
+		fn owned_ads(owner: Hash) -> Vec<AdIndex>;
+		/// Active ads in activation order.
+		fn active_ads(offset: u32, limit: u32) -> Vec<AdIndex>;
+		/// Active ads matching `query`, skipping the first `offset` matches and
+		/// returning at most `limit` of them.
+		fn active_ads_by_targeting(query: TargetingQuery, offset: u32, limit: u32) -> Vec<AdIndex>;
+		/// Budget an ad has left to pay out.
+		fn remaining_budget(adid: AdIndex) -> Option<Balance>;
+		/// Total amount an ad has paid out in rewards.
+		fn rewards_paid(adid: AdIndex) -> Balance;
+		/// Number of rewards a user has received, optionally restricted to one ad.
+		fn user_reward_count(user: Hash, adid: Option<AdIndex>) -> u64;
+		/// Aggregated campaign statistics for an ad.
+		fn campaign_stats(adid: AdIndex) -> Option<CampaignStats<Balance>>;
+		/// Auction ads holding a display slot this epoch with their price per click.
+		/// Since version 2.
+		fn slot_winners() -> Vec<(AdIndex, Balance)>;
+	}
+}
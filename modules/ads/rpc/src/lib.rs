@@ -0,0 +1,134 @@
+//! RPC interface for the ads module.
+
+use std::sync::Arc;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use ads_runtime_api::{AdsApi as AdsRuntimeApi, AdIndex, CampaignStats, TargetingQuery};
+
+#[rpc]
+pub trait AdsApi<BlockHash, Hash, Balance> {
+	#[rpc(name = "ads_ownedAds")]
+	fn owned_ads(&self, owner: Hash, at: Option<BlockHash>) -> Result<Vec<AdIndex>>;
+
+	#[rpc(name = "ads_activeAds")]
+	fn active_ads(&self, offset: u32, limit: u32, at: Option<BlockHash>) -> Result<Vec<AdIndex>>;
+
+	#[rpc(name = "ads_activeAdsByTargeting")]
+	fn active_ads_by_targeting(&self, query: TargetingQuery, offset: u32, limit: u32, at: Option<BlockHash>) -> Result<Vec<AdIndex>>;
+
+	#[rpc(name = "ads_remainingBudget")]
+	fn remaining_budget(&self, adid: AdIndex, at: Option<BlockHash>) -> Result<Option<Balance>>;
+
+	#[rpc(name = "ads_rewardsPaid")]
+	fn rewards_paid(&self, adid: AdIndex, at: Option<BlockHash>) -> Result<Balance>;
+
+	#[rpc(name = "ads_userRewardCount")]
+	fn user_reward_count(&self, user: Hash, adid: Option<AdIndex>, at: Option<BlockHash>) -> Result<u64>;
+
+	#[rpc(name = "ads_campaignStats")]
+	fn campaign_stats(&self, adid: AdIndex, at: Option<BlockHash>) -> Result<Option<CampaignStats<Balance>>>;
+
+	#[rpc(name = "ads_slotWinners")]
+	fn slot_winners(&self, at: Option<BlockHash>) -> Result<Vec<(AdIndex, Balance)>>;
+}
+
+/// A struct that implements the [`AdsApi`].
+pub struct Ads<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Ads<C, B> {
+	/// Create new `Ads` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Ads { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error<E: std::fmt::Debug>(message: &str, e: E) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, Hash, Balance> AdsApi<<Block as BlockT>::Hash, Hash, Balance> for Ads<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: AdsRuntimeApi<Block, Hash, Balance>,
+	Hash: Codec,
+	Balance: Codec,
+{
+	fn owned_ads(&self, owner: Hash, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<AdIndex>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.owned_ads(&at, owner).map_err(|e| runtime_error("Unable to query owned ads.", e))
+	}
+
+	fn active_ads(&self, offset: u32, limit: u32, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<AdIndex>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.active_ads(&at, offset, limit).map_err(|e| runtime_error("Unable to query active ads.", e))
+	}
+
+	fn active_ads_by_targeting(&self, query: TargetingQuery, offset: u32, limit: u32, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<AdIndex>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.active_ads_by_targeting(&at, query, offset, limit).map_err(|e| runtime_error("Unable to query active ads.", e))
+	}
+
+	fn remaining_budget(&self, adid: AdIndex, at: Option<<Block as BlockT>::Hash>) -> Result<Option<Balance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.remaining_budget(&at, adid).map_err(|e| runtime_error("Unable to query remaining budget.", e))
+	}
+
+	fn rewards_paid(&self, adid: AdIndex, at: Option<<Block as BlockT>::Hash>) -> Result<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.rewards_paid(&at, adid).map_err(|e| runtime_error("Unable to query rewards paid.", e))
+	}
+
+	fn user_reward_count(&self, user: Hash, adid: Option<AdIndex>, at: Option<<Block as BlockT>::Hash>) -> Result<u64> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.user_reward_count(&at, user, adid).map_err(|e| runtime_error("Unable to query user rewards.", e))
+	}
+
+	fn campaign_stats(&self, adid: AdIndex, at: Option<<Block as BlockT>::Hash>) -> Result<Option<CampaignStats<Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.campaign_stats(&at, adid).map_err(|e| runtime_error("Unable to query campaign stats.", e))
+	}
+
+	fn slot_winners(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<(AdIndex, Balance)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let supported = api.has_api_with::<dyn AdsRuntimeApi<Block, Hash, Balance>, _>(&at, |version| version >= 2)
+			.map_err(|e| runtime_error("Unable to query the ads api version.", e))?;
+		if !supported {
+			return Err(runtime_error("Slot winners are not available at this block.", "AdsApi version 1"));
+		}
+		api.slot_winners(&at).map_err(|e| runtime_error("Unable to query slot winners.", e))
+	}
+}
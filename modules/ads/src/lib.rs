@@ -1,22 +1,114 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 mod tests;
-mod array_list;
 use codec::{Decode, Encode};
 use sp_std::vec::Vec;
 use frame_support::{
-    decl_event, decl_module, decl_storage, decl_error,ensure,debug
+    decl_event, decl_module, decl_storage, decl_error,ensure,debug,
+    IterableStorageDoubleMap, IterableStorageMap,
+    traits::{Currency, ReservableCurrency, ExistenceRequirement, EnsureOrigin, Get},
+    weights::Weight,
 };
-use sp_runtime::{DispatchResult, traits::{Zero, CheckedSub, CheckedAdd, Hash}};
+use sp_runtime::{DispatchResult, DispatchError, RuntimeDebug, Permill, traits::{Zero, CheckedSub, CheckedAdd, Hash, Saturating}};
 use frame_system::{self as system, ensure_signed};
-use array_list::ArrayList;
+use utilities::{FixedU128, LinkedItem, LinkedList};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 
 pub trait Trait: pallet_balances::Trait + pallet_timestamp::Trait + did::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+    /// Origin allowed to approve or reject ads and resolve reports.
+    type ModeratorOrigin: EnsureOrigin<Self::Origin>;
+    /// Amount reserved from a user reporting an ad.
+    type ReportBond: Get<Self::Balance>;
+    /// Amount taken from an ad's budget when a report against it is upheld.
+    type ReportSlash: Get<Self::Balance>;
+    /// Number of blocks in a slot auction epoch.
+    type EpochLength: Get<Self::BlockNumber>;
+    /// Last blocks of an epoch in which sealed bids are revealed.
+    type RevealPeriod: Get<Self::BlockNumber>;
+    /// Minimum bid per click, also the price paid by a winner without competition.
+    type MinBid: Get<Self::Balance>;
+    /// Most ads bidding, open or sealed, in one epoch.
+    type MaxBids: Get<u32>;
+    /// Amount reserved from an account registering as an agent.
+    type AgentStake: Get<Self::Balance>;
+    /// Blocks an unregistering agent's stake stays slashable before it can be withdrawn.
+    type AgentUnbondPeriod: Get<Self::BlockNumber>;
+    /// Most ads a single agent can be assigned to.
+    type MaxAgentAds: Get<u32>;
+    /// Highest share of a payout an agent may keep as fee.
+    type MaxAgentFee: Get<Permill>;
+    /// Exchange rates for click fees not priced in the native currency.
+    type Prices: PriceProvider<Self::Moment>;
+    /// Oldest price, in milliseconds, still used to convert a click fee.
+    type MaxPriceAge: Get<Self::Moment>;
+}
+
+/// Native currency paid for one unit of another currency.
+pub type Price = FixedU128;
+
+/// Source of the exchange rates used to convert click fees at payout time.
+pub trait PriceProvider<Moment> {
+    /// Native currency paid for one unit of `currency` and when that rate was fed.
+    fn native_price(currency: &FeeCurrency) -> Option<(Price, Moment)>;
+}
+
+impl<Moment> PriceProvider<Moment> for () {
+    fn native_price(_: &FeeCurrency) -> Option<(Price, Moment)> { None }
 }
 
 pub type AdIndex = u64;
-pub type ActiveIndex = u64;
-type AdsActiveList = ArrayList<AdsActives, AdIndex, AdsActiveCount>;
+pub type TopicId = u32;
+pub type EpochIndex = u32;
+type AdsActiveList = LinkedList<AdsActives, AdIndex, AdsActiveCount>;
+
+/// max topic ids an ad can target
+pub const MAX_TOPICS: usize = 16;
+/// max regions or languages an ad can target
+pub const MAX_TARGETS: usize = 32;
+/// max ads returned by a single targeting query
+pub const MAX_QUERY_LIMIT: u32 = 100;
+/// display slots auctioned per epoch until changed by moderators
+pub const DEFAULT_SLOT_COUNT: u32 = 3;
+/// upper bound of display slots per epoch
+pub const MAX_SLOTS: u32 = 20;
+/// layout version of the stored ads, older layouts are migrated on runtime upgrade
+pub const STORAGE_VERSION: u32 = 1;
+
+/// Who an ad should be shown to. Empty lists mean no restriction.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Targeting {
+    pub topics: Vec<TopicId>,
+    /// ISO 3166-1 alpha-2 region codes, e.g. `b"CN"`
+    pub regions: Vec<Vec<u8>>,
+    /// ISO 639-1 language codes, e.g. `b"zh"`
+    pub languages: Vec<Vec<u8>>,
+    /// did type of the users the ad is for
+    pub did_type: Option<Vec<u8>>,
+}
+
+/// Campaign analytics returned by the runtime API.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct CampaignStats<Balance> {
+    pub total_amount: Balance,
+    pub spend_amount: Balance,
+    pub remaining: Balance,
+    pub single_click_fee: Balance,
+    pub clicks: u64,
+    pub unique_users: u64,
+    pub active: bool,
+}
+
+/// Filter used to look up active ads. `None` fields match everything.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct TargetingQuery {
+    pub topic: Option<TopicId>,
+    pub region: Option<Vec<u8>>,
+    pub language: Option<Vec<u8>>,
+    pub did_type: Option<Vec<u8>>,
+}
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
@@ -29,10 +121,111 @@ pub struct AdsMetadata<Balance, Moment> {
     display_page: Vec<u8>,
     landing_page: Option<Vec<u8>>,
     create_time: Moment,
-    active: Option<ActiveIndex>,
+    active: bool,
+    distribute_type: DistributeType,
+    targeting: Targeting,
+    review: ReviewStatus,
+    pricing: PricingMode,
+    fee_currency: FeeCurrency,
+}
+
+/// `AdsMetadata` as stored before storage version 1.
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+pub struct OldAdsMetadata<Balance, Moment> {
+    advertiser: Vec<u8>,
+    topic: Vec<u8>,
+    total_amount: Balance,
+    spend_amount: Balance,
+    single_click_fee: Balance,
+    display_page: Vec<u8>,
+    landing_page: Option<Vec<u8>>,
+    create_time: Moment,
+    active: Option<u64>,
     distribute_type: DistributeType,
 }
 
+/// Currency a fixed `single_click_fee` is denominated in. Budgets and payouts
+/// are always in the native currency, with the same precision.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Debug)]
+pub enum FeeCurrency {
+    PRM,
+    USD,
+}
+
+impl Default for FeeCurrency {
+    fn default() -> Self { FeeCurrency::PRM }
+}
+
+/// How the per click price of an ad is decided.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Debug)]
+pub enum PricingMode {
+    /// the advertiser pays `single_click_fee`
+    Fixed,
+    /// the ad competes for display slots and pays the second price
+    Auction,
+}
+
+impl Default for PricingMode {
+    fn default() -> Self { PricingMode::Fixed }
+}
+
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Debug)]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl Default for ReviewStatus {
+    fn default() -> Self { ReviewStatus::Pending }
+}
+
+/// Creative change waiting for moderation.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Creative {
+    pub display_page: Vec<u8>,
+    pub landing_page: Option<Vec<u8>>,
+}
+
+/// A bonded report against an ad.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Report<Balance> {
+    pub reason: Vec<u8>,
+    pub bond: Balance,
+}
+
+/// A registered agent distributing rewards for `AGENT` type ads.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct AgentInfo<Balance> {
+    /// stake reserved from the agent account
+    pub stake: Balance,
+    /// share of each payout kept by the agent
+    pub fee: Permill,
+}
+
+/// Role of a member in an org advertiser account, ordered by the permissions it grants.
+#[derive(Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub enum Role {
+    /// read only, kept on chain for dashboards
+    Viewer,
+    /// runs the org's campaigns and moves budget within its spend limit
+    CampaignManager,
+    /// everything a campaign manager can do, plus managing members
+    Admin,
+}
+
+impl Default for Role {
+    fn default() -> Self { Role::Viewer }
+}
+
+/// A member did of an org advertiser account.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct OrgMember<Balance> {
+    pub role: Role,
+    /// budget the member may withdraw or pay out across the org's ads, `None` for no limit
+    pub spend_limit: Option<Balance>,
+}
+
 #[derive(Encode, Decode , PartialEq, Eq, Clone, Debug)]
 pub enum DistributeType{
     ADVERTISER,
@@ -55,7 +248,7 @@ decl_error! {
 		InvalidGroupName,
 		/// you are not own the ad
 		NotOwner,
-        ///agent type ad need contract account signed
+        ///agent type ad need an assigned agent account signed
 		NeedAgentAccountSigned,
         /// create or deposit ad min balance
 		MineDeposit,
@@ -69,6 +262,62 @@ decl_error! {
 		NotADAccount,
         ///withdraw balance time not reach
         TimeNotReach,
+        /// too many topics, regions or languages
+        TooManyTargets,
+        /// ad has not been approved by moderators
+        NotApproved,
+        /// ad is not waiting for moderation
+        NotPending,
+        /// you have already reported this ad
+        AlreadyReported,
+        /// report does not exist
+        ReportNotExists,
+        /// ad is not in auction pricing mode
+        NotAuctionMode,
+        /// bid is below the minimum bid
+        BidTooLow,
+        /// remaining budget can not pay a single click at the bid
+        BidExceedsBudget,
+        /// bids can not be placed or committed during the reveal period
+        CommitPeriodOver,
+        /// too many ads are bidding in this epoch
+        TooManyBids,
+        /// sealed bids can only be revealed during the reveal period
+        NotRevealPeriod,
+        /// no sealed bid committed for the ad
+        BidNotCommitted,
+        /// revealed bid does not match the commitment
+        InvalidReveal,
+        /// auction ad did not win a slot in the current epoch
+        NoSlot,
+        /// too many slots
+        TooManySlots,
+        /// account is already a registered agent
+        AlreadyAgent,
+        /// account is not a registered agent
+        AgentNotExists,
+        /// agent fee is higher than allowed
+        AgentFeeTooHigh,
+        /// no exchange rate for the fee currency
+        PriceUnavailable,
+        /// exchange rate for the fee currency is too old
+        PriceStale,
+        /// your role in the org does not allow this
+        NoPermission,
+        /// member did is not part of the org
+        MemberNotExists,
+        /// the org can not be its own member
+        InvalidMember,
+        /// member spend limit reached
+        SpendLimitExceeded,
+        /// agent is unregistering
+        AgentUnbonding,
+        /// agent stake is still unbonding
+        StillUnbonding,
+        /// agent is assigned to too many ads
+        TooManyAgentAds,
+        /// the contract did can not be an agent
+        InvalidAgent,
 	}
 }
 
@@ -78,11 +327,55 @@ decl_storage! {
         pub Contract get(fn contract) config(): T::AccountId;
         pub MinDeposit get(fn min_deposit) config(): T::Balance;
         pub AdsRecords get(fn ads_records): map hasher(twox_64_concat) AdIndex => AdsMetadata<T::Balance, T::Moment>;
-        pub AdsActives get(fn ads_actives): map hasher(twox_64_concat) ActiveIndex => Option<AdIndex>;
-        pub AdsActiveCount get(fn ads_active_count): Option<ActiveIndex>;
+        pub AdsActives get(fn ads_actives): map hasher(twox_64_concat) Option<AdIndex> => Option<LinkedItem<AdIndex>>;
+        pub AdsActiveCount get(fn ads_active_count): u64;
         pub AdsOwner get(fn ads_owner):map hasher(twox_64_concat) AdIndex => T::Hash;
         pub AllAdsCount get(fn all_ads_count): AdIndex;
         pub OwnedAds get(fn owned_ads):map hasher(twox_64_concat) T::Hash => Vec<AdIndex>;
+        /// active ads indexed by the topics they target
+        pub TopicAds get(fn topic_ads): double_map hasher(twox_64_concat) TopicId, hasher(twox_64_concat) AdIndex => ();
+        /// rewards paid by an ad to each user
+        pub AdUserRewards get(fn ad_user_rewards): double_map hasher(twox_64_concat) AdIndex, hasher(twox_64_concat) T::Hash => u32;
+        /// total rewards received by a user across all ads
+        pub UserRewardCount get(fn user_reward_count): map hasher(twox_64_concat) T::Hash => u64;
+        /// total rewards paid by an ad
+        pub AdClicks get(fn ad_clicks): map hasher(twox_64_concat) AdIndex => u64;
+        /// distinct users rewarded by an ad
+        pub AdUniqueUsers get(fn ad_unique_users): map hasher(twox_64_concat) AdIndex => u64;
+        /// creative changes of approved ads waiting for moderation
+        pub PendingCreatives get(fn pending_creatives): map hasher(twox_64_concat) AdIndex => Option<Creative>;
+        /// open reports against an ad
+        pub Reports get(fn reports): double_map hasher(twox_64_concat) AdIndex, hasher(twox_64_concat) T::AccountId => Option<Report<T::Balance>>;
+        /// display slots auctioned per epoch
+        pub SlotCount get(fn slot_count): u32 = DEFAULT_SLOT_COUNT;
+        /// epoch currently taking bids
+        pub CurrentEpoch get(fn current_epoch): EpochIndex;
+        /// open and revealed bids per click of an epoch
+        pub Bids get(fn bids): double_map hasher(twox_64_concat) EpochIndex, hasher(twox_64_concat) AdIndex => Option<T::Balance>;
+        /// sealed bid commitments of an epoch, `hash(adid, amount, salt)`
+        pub SealedBids get(fn sealed_bids): double_map hasher(twox_64_concat) EpochIndex, hasher(twox_64_concat) AdIndex => Option<T::Hash>;
+        /// ads bidding in the current epoch, open or sealed
+        pub BidCount get(fn bid_count): u32;
+        /// winners of the last cleared epoch with the price they pay per click, highest bid first
+        pub SlotWinners get(fn slot_winners): Vec<(AdIndex, T::Balance)>;
+        /// per click price of the ads holding a slot
+        pub SlotPrice get(fn slot_price): map hasher(twox_64_concat) AdIndex => Option<T::Balance>;
+        /// registered agents
+        pub Agents get(fn agents): map hasher(twox_64_concat) T::AccountId => Option<AgentInfo<T::Balance>>;
+        /// agents chosen by advertisers to distribute an ad
+        pub AdAgents get(fn ad_agents): double_map hasher(twox_64_concat) AdIndex, hasher(twox_64_concat) T::AccountId => ();
+        /// ads an agent is assigned to, the reverse of `AdAgents`
+        pub AgentAds get(fn agent_ads): double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) AdIndex => ();
+        /// number of ads an agent is assigned to
+        pub AgentAdsCount get(fn agent_ads_count): map hasher(twox_64_concat) T::AccountId => u32;
+        /// block from which an unregistering agent can withdraw its stake
+        pub AgentUnbonding get(fn agent_unbonding): map hasher(twox_64_concat) T::AccountId => Option<T::BlockNumber>;
+        /// member dids of org advertiser accounts, the org did owns the ads
+        pub OrgMembers get(fn org_members): double_map hasher(twox_64_concat) T::Hash, hasher(twox_64_concat) T::Hash => Option<OrgMember<T::Balance>>;
+        /// org budget moved by a member did, kept when the member is removed and added again
+        pub MemberSpent get(fn member_spent): double_map hasher(twox_64_concat) T::Hash, hasher(twox_64_concat) T::Hash => T::Balance;
+        /// layout version of the stored ads
+        pub StorageVersion get(fn storage_version) build(|_: &GenesisConfig<T>| STORAGE_VERSION): u32;
     }
 }
 
@@ -90,6 +383,8 @@ decl_event! {
   pub enum Event<T>
   where
     <T as frame_system::Trait>::Hash,
+    <T as frame_system::Trait>::AccountId,
+    <T as frame_system::Trait>::BlockNumber,
     <T as pallet_balances::Trait>::Balance,
     {
         Published(Hash, Hash, Balance),
@@ -99,6 +394,36 @@ decl_event! {
         Withdraw(Hash, Balance),
         Distributed(Hash, Hash, Balance),
         AdsUpdated(AdIndex),
+        CreativeSubmitted(AdIndex),
+        Approved(AdIndex),
+        Rejected(AdIndex, Vec<u8>),
+        Reported(AdIndex, AccountId, Balance),
+        /// adid, reporter, upheld, amount slashed
+        ReportResolved(AdIndex, AccountId, bool, Balance),
+        PricingModeChanged(AdIndex, PricingMode),
+        BidPlaced(EpochIndex, AdIndex, Balance),
+        BidCommitted(EpochIndex, AdIndex),
+        /// epoch, adid, price per click
+        SlotWon(EpochIndex, AdIndex, Balance),
+        EpochCleared(EpochIndex),
+        SlotCountChanged(u32),
+        AgentRegistered(AccountId, Balance, Permill),
+        AgentFeeChanged(AccountId, Permill),
+        AgentUnregistered(AccountId),
+        /// agent, block its stake can be withdrawn from
+        AgentUnbonding(AccountId, BlockNumber),
+        AgentAssigned(AdIndex, AccountId),
+        AgentUnassigned(AdIndex, AccountId),
+        /// agent, amount slashed from its stake
+        AgentSlashed(AccountId, Balance),
+        /// agent, adid, fee kept by the agent
+        AgentFeePaid(AccountId, AdIndex, Balance),
+        FeeCurrencyChanged(AdIndex, FeeCurrency),
+        /// adid, click fee in the fee currency, amount paid, rate used
+        FeeConverted(AdIndex, Balance, Balance, Price),
+        /// org, member, role, spend limit
+        MemberSet(Hash, Hash, Role, Option<Balance>),
+        MemberRemoved(Hash, Hash),
     }
 }
 
@@ -106,11 +431,39 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
+        const EpochLength: T::BlockNumber = T::EpochLength::get();
+        const RevealPeriod: T::BlockNumber = T::RevealPeriod::get();
+        const MinBid: T::Balance = T::MinBid::get();
+        const MaxBids: u32 = T::MaxBids::get();
+        const AgentStake: T::Balance = T::AgentStake::get();
+        const AgentUnbondPeriod: T::BlockNumber = T::AgentUnbondPeriod::get();
+        const MaxAgentAds: u32 = T::MaxAgentAds::get();
+        const MaxAgentFee: Permill = T::MaxAgentFee::get();
+        const MaxPriceAge: T::Moment = T::MaxPriceAge::get();
+
+        fn on_runtime_upgrade() -> Weight {
+            if Self::storage_version() >= STORAGE_VERSION {
+                return T::DbWeight::get().reads(1);
+            }
+            let ads = Self::migrate();
+            StorageVersion::put(STORAGE_VERSION);
+            T::DbWeight::get().reads_writes(ads + 1, ads + 1)
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let epoch_length = T::EpochLength::get();
+            if !epoch_length.is_zero() && (now % epoch_length).is_zero() {
+                return Self::clear_epoch();
+            }
+            0
+        }
+
         #[weight = 0]
-        fn publish(origin, name: Vec<u8>, topic: Vec<u8>, total_amount: T::Balance, single_click_fee: T::Balance,display_page:Vec<u8>,landing_page:Option<Vec<u8>>,distribute_type:DistributeType) {
+        fn publish(origin, name: Vec<u8>, topic: Vec<u8>, total_amount: T::Balance, single_click_fee: T::Balance,display_page:Vec<u8>,landing_page:Option<Vec<u8>>,distribute_type:DistributeType,targeting:Targeting) {
             let sender = ensure_signed(origin)?;
 
             ensure!(total_amount >= Self::min_deposit(), Error::<T>::MineDeposit);
+            Self::check_targeting(&targeting)?;
             let (from_key, did) = <did::Module<T>>::identity(sender).ok_or(<did::Error<T>>::DidNotExists)?;
             ensure!(Self::is_sub(&did[..2] , "4".as_bytes()),Error::<T>::NotADAccount);
             let create_time = <pallet_timestamp::Module<T>>::get();
@@ -127,26 +480,30 @@ decl_module! {
                 display_page,
                 landing_page,
                 create_time,
-                active: None,
+                active: false,
                 distribute_type,
+                targeting,
+                review: ReviewStatus::Pending,
+                pricing: PricingMode::Fixed,
+                fee_currency: FeeCurrency::PRM,
             };
             let adid = Self::all_ads_count();
+            // ads go live once moderators approve them
             Self::create_ad(from_key,&adid,ads_metadata)?;
-            Self::active_ad(&adid)?;
             Self::deposit_event(RawEvent::Published(from_key, contract, total_amount));
-            Self::deposit_event(RawEvent::Active(adid));
         }
         #[weight = 0 ]
         fn active(origin,adid:AdIndex){
             let sender = ensure_signed(origin)?;
-            Self::check_ad_owner(&sender,&adid)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            ensure!(Self::ads_records(adid).review == ReviewStatus::Approved, Error::<T>::NotApproved);
             Self::active_ad(&adid)?;
             Self::deposit_event(RawEvent::Active(adid));
         }
         #[weight = 0]
         fn pause(origin,adid:AdIndex){
             let sender = ensure_signed(origin)?;
-            Self::check_ad_owner(&sender,&adid)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
             Self::pause_ad(&adid)?;
             Self::deposit_event(RawEvent::Pause(adid));
         }
@@ -170,8 +527,7 @@ decl_module! {
         #[weight = 0]
         fn withdraw(origin, adid:AdIndex, value: T::Balance, memo: Vec<u8>) {
             let sender = ensure_signed(origin)?;
-            Self::check_ad_owner(&sender,&adid)?;
-            let (from_key, _) = <did::Module<T>>::identity(sender).ok_or(<did::Error<T>>::DidNotExists)?;
+            let (from_key, owner) = Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
             let mut ads_metadata = Self::ads_records(adid);
             let now = <pallet_timestamp::Module<T>>::get();
             let locked_period:T::Moment = (30*24*60*60*1000u32).into();
@@ -180,43 +536,67 @@ decl_module! {
             let total_amount = ads_metadata.total_amount.checked_sub(&value).ok_or(Error::<T>::Overflow)?;
             ensure!(ads_metadata.spend_amount <= total_amount , Error::<T>::NotEnoughBalance);
             let (contract_key, _) = <did::Module<T>>::identity(Self::contract()).ok_or(Error::<T>::ContractDidNotExists)?;
-            <did::Module<T>>::transfer_by_did(contract_key, from_key, value, memo)?;
+            Self::note_member_spend(&owner, &from_key, value)?;
+            // budgets always go back to the owner did, even when a member withdraws them
+            <did::Module<T>>::transfer_by_did(contract_key, owner, value, memo)?;
             // update ads metadata
             ads_metadata.total_amount = total_amount;
              <AdsRecords<T>>::insert(adid, ads_metadata);
-            Self::deposit_event(RawEvent::Withdraw(from_key, value));
+            Self::deposit_event(RawEvent::Withdraw(owner, value));
         }
 //
         #[weight = 0]
 		fn distribute(origin,adid: AdIndex,user: T::Hash) {
 			let sender = ensure_signed(origin)?;
 			let mut ads_metadata = <AdsRecords<T>>::get(adid);
-			ensure!(ads_metadata.active.is_some(),Error::<T>::NotActive);
+			ensure!(ads_metadata.active,Error::<T>::NotActive);
+			let mut spender = None;
 			match ads_metadata.distribute_type{
 			    DistributeType::ADVERTISER=>{
-                    Self::check_ad_owner(&sender,&adid)?;
+                    spender = Some(Self::check_ad_role(&sender,&adid,Role::CampaignManager)?);
 			    },
 			    DistributeType::AGENT=>{
-                    ensure!(sender == Self::contract(),Error::<T>::NeedAgentAccountSigned);
+                    ensure!(<Agents<T>>::contains_key(&sender), Error::<T>::NeedAgentAccountSigned);
+                    ensure!(<AdAgents<T>>::contains_key(adid, &sender), Error::<T>::NeedAgentAccountSigned);
 			    }
 			}
-            let value = ads_metadata.single_click_fee;
+            let (value, rate) = match ads_metadata.pricing {
+                PricingMode::Fixed => Self::native_click_fee(&ads_metadata)?,
+                PricingMode::Auction => (Self::slot_price(adid).ok_or(Error::<T>::NoSlot)?, None),
+            };
+            let single_click_fee = ads_metadata.single_click_fee;
             let spend = ads_metadata.spend_amount.checked_add(&value).ok_or(Error::<T>::Overflow)?;
 			ensure!(spend <= ads_metadata.total_amount, Error::<T>::NotEnoughBalance);
             let (contract_key, _) = <did::Module<T>>::identity(Self::contract()).ok_or(Error::<T>::ContractDidNotExists)?;
             ensure!(<did::Metadata<T>>::contains_key(user),<did::Error<T>>::DidNotExists);
-            let (from_key, _) = <did::Module<T>>::identity(sender).ok_or(<did::Error<T>>::DidNotExists)?;
-			<did::Module<T>>::transfer_by_did(contract_key, user, value, "ads看广告收益".as_bytes().to_vec())?;
+            let (from_key, _) = <did::Module<T>>::identity(&sender).ok_or(<did::Error<T>>::DidNotExists)?;
+            let agent_fee = match ads_metadata.distribute_type {
+                DistributeType::AGENT => Self::agents(&sender).map(|agent| agent.fee * value).unwrap_or_else(Zero::zero),
+                DistributeType::ADVERTISER => Zero::zero(),
+            };
+            let reward = value.checked_sub(&agent_fee).ok_or(Error::<T>::Overflow)?;
+            if let Some((member, owner)) = spender {
+                Self::note_member_spend(&owner, &member, value)?;
+            }
+			<did::Module<T>>::transfer_by_did(contract_key, user, reward, "ads看广告收益".as_bytes().to_vec())?;
+            if !agent_fee.is_zero() {
+                <did::Module<T>>::transfer_by_did(contract_key, from_key, agent_fee, "广告代理费".as_bytes().to_vec())?;
+                Self::deposit_event(RawEvent::AgentFeePaid(sender, adid, agent_fee));
+            }
 			// update ads metadata
 			ads_metadata.spend_amount = spend;
 			<AdsRecords<T>>::insert(adid, ads_metadata);
-			Self::deposit_event(RawEvent::Distributed(from_key, user, value));
+			Self::note_reward(&adid, &user);
+			if let Some(rate) = rate {
+			    Self::deposit_event(RawEvent::FeeConverted(adid, single_click_fee, value, rate));
+			}
+			Self::deposit_event(RawEvent::Distributed(from_key, user, reward));
 		}
 //
        #[weight = 0]
-		fn update_ads(origin, adid:AdIndex,name:Option<Vec<u8>>,single_click_fee: Option<T::Balance>,display_page:Option<Vec<u8>>,landing_page:Option<Vec<u8>>) {
+		fn update_ads(origin, adid:AdIndex,name:Option<Vec<u8>>,single_click_fee: Option<T::Balance>,display_page:Option<Vec<u8>>,landing_page:Option<Vec<u8>>,targeting:Option<Targeting>) {
 			let sender = ensure_signed(origin)?;
-            Self::check_ad_owner(&sender,&adid)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
 			// update ads records
             let mut ads_metadata = Self::ads_records(adid);
             if name.is_some(){
@@ -225,23 +605,379 @@ decl_module! {
             if single_click_fee.is_some(){
                 ads_metadata.single_click_fee = single_click_fee.unwrap();
             }
-            if display_page.is_some(){
-                ads_metadata.display_page = display_page.unwrap();
+            // creative changes have to be reviewed again before they are shown
+            if display_page.is_some() || landing_page.is_some() {
+                let creative = Creative {
+                    display_page: display_page.unwrap_or_else(|| ads_metadata.display_page.clone()),
+                    landing_page: landing_page.or_else(|| ads_metadata.landing_page.clone()),
+                };
+                if ads_metadata.review == ReviewStatus::Approved {
+                    <PendingCreatives>::insert(adid, creative);
+                    Self::deposit_event(RawEvent::CreativeSubmitted(adid));
+                } else {
+                    ads_metadata.display_page = creative.display_page;
+                    ads_metadata.landing_page = creative.landing_page;
+                    ads_metadata.review = ReviewStatus::Pending;
+                }
             }
-            if landing_page.is_some(){
-                ads_metadata.landing_page = landing_page;
+            if let Some(targeting) = targeting {
+                Self::check_targeting(&targeting)?;
+                if ads_metadata.active {
+                    Self::remove_topic_index(&adid, &ads_metadata.targeting);
+                    Self::insert_topic_index(&adid, &targeting);
+                }
+                ads_metadata.targeting = targeting;
             }
 			<AdsRecords<T>>::insert(adid, ads_metadata);
 			Self::deposit_event(RawEvent::AdsUpdated(adid));
 		}
+
+        /// Approve a pending ad, or the pending creative change of an approved ad.
+        #[weight = 0]
+        fn approve(origin, adid: AdIndex) {
+            T::ModeratorOrigin::ensure_origin(origin)?;
+            ensure!(<AdsRecords<T>>::contains_key(adid), Error::<T>::ADNotExists);
+            let mut ads_metadata = Self::ads_records(adid);
+            if let Some(creative) = <PendingCreatives>::take(adid) {
+                ads_metadata.display_page = creative.display_page;
+                ads_metadata.landing_page = creative.landing_page;
+                <AdsRecords<T>>::insert(adid, ads_metadata);
+            } else {
+                ensure!(ads_metadata.review == ReviewStatus::Pending, Error::<T>::NotPending);
+                ads_metadata.review = ReviewStatus::Approved;
+                <AdsRecords<T>>::insert(adid, ads_metadata);
+                Self::active_ad(&adid)?;
+                Self::deposit_event(RawEvent::Active(adid));
+            }
+            Self::deposit_event(RawEvent::Approved(adid));
+        }
+
+        /// Reject a pending ad, or drop the pending creative change of an approved ad.
+        #[weight = 0]
+        fn reject(origin, adid: AdIndex, reason: Vec<u8>) {
+            T::ModeratorOrigin::ensure_origin(origin)?;
+            ensure!(<AdsRecords<T>>::contains_key(adid), Error::<T>::ADNotExists);
+            if <PendingCreatives>::take(adid).is_none() {
+                let mut ads_metadata = Self::ads_records(adid);
+                ensure!(ads_metadata.review == ReviewStatus::Pending, Error::<T>::NotPending);
+                ads_metadata.review = ReviewStatus::Rejected;
+                <AdsRecords<T>>::insert(adid, ads_metadata);
+            }
+            Self::deposit_event(RawEvent::Rejected(adid, reason));
+        }
+
+        /// Report an ad, reserving `ReportBond` from the reporter.
+        #[weight = 0]
+        fn report(origin, adid: AdIndex, reason: Vec<u8>) {
+            let sender = ensure_signed(origin)?;
+            ensure!(<AdsRecords<T>>::contains_key(adid), Error::<T>::ADNotExists);
+            ensure!(!<Reports<T>>::contains_key(adid, &sender), Error::<T>::AlreadyReported);
+            let bond = T::ReportBond::get();
+            <pallet_balances::Module<T>>::reserve(&sender, bond)?;
+            <Reports<T>>::insert(adid, &sender, Report { reason, bond });
+            Self::deposit_event(RawEvent::Reported(adid, sender, bond));
+        }
+
+        /// Resolve a report. Upheld reports pause the ad and send it back to review, slash
+        /// `ReportSlash` from its budget to the reporter and return the bond, otherwise the
+        /// bond is slashed.
+        #[weight = 0]
+        fn resolve_report(origin, adid: AdIndex, reporter: T::AccountId, upheld: bool) {
+            T::ModeratorOrigin::ensure_origin(origin)?;
+            let report = <Reports<T>>::take(adid, &reporter).ok_or(Error::<T>::ReportNotExists)?;
+            let mut slashed = Zero::zero();
+            if upheld {
+                <pallet_balances::Module<T>>::unreserve(&reporter, report.bond);
+                if Self::ads_records(adid).active {
+                    Self::pause_ad(&adid)?;
+                    Self::deposit_event(RawEvent::Pause(adid));
+                }
+                let mut ads_metadata = Self::ads_records(adid);
+                let remaining = ads_metadata.total_amount.checked_sub(&ads_metadata.spend_amount).unwrap_or_else(Zero::zero);
+                slashed = T::ReportSlash::get().min(remaining);
+                if !slashed.is_zero() {
+                    <pallet_balances::Module<T> as Currency<_>>::transfer(&Self::contract(), &reporter, slashed, ExistenceRequirement::KeepAlive)?;
+                    ads_metadata.total_amount = ads_metadata.total_amount.checked_sub(&slashed).ok_or(Error::<T>::Overflow)?;
+                }
+                // the advertiser can't re-activate it until moderators approve it again
+                ads_metadata.review = ReviewStatus::Pending;
+                <AdsRecords<T>>::insert(adid, ads_metadata);
+            } else {
+                let _ = <pallet_balances::Module<T>>::slash_reserved(&reporter, report.bond);
+            }
+            Self::deposit_event(RawEvent::ReportResolved(adid, reporter, upheld, slashed));
+        }
+
+        /// Switch an ad between fixed price and slot auction mode.
+        #[weight = 0]
+        fn set_pricing_mode(origin, adid: AdIndex, pricing: PricingMode) {
+            let sender = ensure_signed(origin)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            let mut ads_metadata = Self::ads_records(adid);
+            ads_metadata.pricing = pricing.clone();
+            <AdsRecords<T>>::insert(adid, ads_metadata);
+            Self::deposit_event(RawEvent::PricingModeChanged(adid, pricing));
+        }
+
+        /// Place an open bid per click for the current epoch, replacing any earlier bid.
+        #[weight = 0]
+        fn bid(origin, adid: AdIndex, amount: T::Balance) {
+            let sender = ensure_signed(origin)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            ensure!(!Self::in_reveal_period(), Error::<T>::CommitPeriodOver);
+            Self::check_bid(&adid, amount)?;
+            let epoch = Self::current_epoch();
+            Self::note_bidder(epoch, &adid)?;
+            <Bids<T>>::insert(epoch, adid, amount);
+            Self::deposit_event(RawEvent::BidPlaced(epoch, adid, amount));
+        }
+
+        /// Commit a sealed bid `hash(adid, amount, salt)` for the current epoch.
+        #[weight = 0]
+        fn commit_bid(origin, adid: AdIndex, commitment: T::Hash) {
+            let sender = ensure_signed(origin)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            ensure!(Self::ads_records(adid).pricing == PricingMode::Auction, Error::<T>::NotAuctionMode);
+            ensure!(!Self::in_reveal_period(), Error::<T>::CommitPeriodOver);
+            let epoch = Self::current_epoch();
+            Self::note_bidder(epoch, &adid)?;
+            <SealedBids<T>>::insert(epoch, adid, commitment);
+            Self::deposit_event(RawEvent::BidCommitted(epoch, adid));
+        }
+
+        /// Reveal a sealed bid during the last `RevealPeriod` blocks of the epoch.
+        #[weight = 0]
+        fn reveal_bid(origin, adid: AdIndex, amount: T::Balance, salt: Vec<u8>) {
+            let sender = ensure_signed(origin)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            ensure!(Self::in_reveal_period(), Error::<T>::NotRevealPeriod);
+            let epoch = Self::current_epoch();
+            let commitment = <SealedBids<T>>::get(epoch, adid).ok_or(Error::<T>::BidNotCommitted)?;
+            ensure!(T::Hashing::hash_of(&(adid, amount, salt)) == commitment, Error::<T>::InvalidReveal);
+            Self::check_bid(&adid, amount)?;
+            <SealedBids<T>>::remove(epoch, adid);
+            <Bids<T>>::insert(epoch, adid, amount);
+            Self::deposit_event(RawEvent::BidPlaced(epoch, adid, amount));
+        }
+
+        /// Price a fixed click fee in another currency, converted to the native
+        /// currency at payout time.
+        #[weight = 0]
+        fn set_fee_currency(origin, adid: AdIndex, fee_currency: FeeCurrency) {
+            let sender = ensure_signed(origin)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            let mut ads_metadata = Self::ads_records(adid);
+            ads_metadata.fee_currency = fee_currency.clone();
+            <AdsRecords<T>>::insert(adid, ads_metadata);
+            Self::deposit_event(RawEvent::FeeCurrencyChanged(adid, fee_currency));
+        }
+
+        /// Register as an agent, reserving `AgentStake`.
+        #[weight = 0]
+        fn register_agent(origin, fee: Permill) {
+            let sender = ensure_signed(origin)?;
+            ensure!(!<Agents<T>>::contains_key(&sender), Error::<T>::AlreadyAgent);
+            ensure!(fee <= T::MaxAgentFee::get(), Error::<T>::AgentFeeTooHigh);
+            // fees are paid from the contract did, it can't pay them to itself
+            let (agent_key, _) = <did::Module<T>>::identity(&sender).ok_or(<did::Error<T>>::DidNotExists)?;
+            let (contract_key, _) = <did::Module<T>>::identity(Self::contract()).ok_or(Error::<T>::ContractDidNotExists)?;
+            ensure!(agent_key != contract_key, Error::<T>::InvalidAgent);
+            let stake = T::AgentStake::get();
+            <pallet_balances::Module<T>>::reserve(&sender, stake)?;
+            <Agents<T>>::insert(&sender, AgentInfo { stake, fee });
+            Self::deposit_event(RawEvent::AgentRegistered(sender, stake, fee));
+        }
+
+        #[weight = 0]
+        fn set_agent_fee(origin, fee: Permill) {
+            let sender = ensure_signed(origin)?;
+            ensure!(fee <= T::MaxAgentFee::get(), Error::<T>::AgentFeeTooHigh);
+            let mut info = Self::agents(&sender).ok_or(Error::<T>::AgentNotExists)?;
+            info.fee = fee;
+            <Agents<T>>::insert(&sender, info);
+            Self::deposit_event(RawEvent::AgentFeeChanged(sender, fee));
+        }
+
+        /// Leave the agent registry. The ad assignments are dropped at once, the stake
+        /// stays slashable for `AgentUnbondPeriod` blocks before it can be withdrawn.
+        #[weight = <Module<T>>::clear_agent_ads_weight()]
+        fn unregister_agent(origin) {
+            let sender = ensure_signed(origin)?;
+            ensure!(<Agents<T>>::contains_key(&sender), Error::<T>::AgentNotExists);
+            ensure!(!<AgentUnbonding<T>>::contains_key(&sender), Error::<T>::AgentUnbonding);
+            Self::clear_agent_ads(&sender);
+            let unbonded_at = <frame_system::Module<T>>::block_number() + T::AgentUnbondPeriod::get();
+            <AgentUnbonding<T>>::insert(&sender, unbonded_at);
+            Self::deposit_event(RawEvent::AgentUnbonding(sender, unbonded_at));
+        }
+
+        /// Get back what is left of the stake once unbonded.
+        #[weight = T::DbWeight::get().reads_writes(3, 4)]
+        fn withdraw_agent_stake(origin) {
+            let sender = ensure_signed(origin)?;
+            let unbonded_at = Self::agent_unbonding(&sender).ok_or(Error::<T>::AgentNotExists)?;
+            ensure!(<frame_system::Module<T>>::block_number() >= unbonded_at, Error::<T>::StillUnbonding);
+            Self::remove_agent_of(&sender)?;
+        }
+
+        /// Allow `agent` to distribute rewards of an `AGENT` type ad.
+        #[weight = 0]
+        fn assign_agent(origin, adid: AdIndex, agent: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            ensure!(<Agents<T>>::contains_key(&agent), Error::<T>::AgentNotExists);
+            ensure!(!<AgentUnbonding<T>>::contains_key(&agent), Error::<T>::AgentUnbonding);
+            if !<AgentAds<T>>::contains_key(&agent, adid) {
+                let count = Self::agent_ads_count(&agent);
+                ensure!(count < T::MaxAgentAds::get(), Error::<T>::TooManyAgentAds);
+                <AgentAdsCount<T>>::insert(&agent, count + 1);
+            }
+            <AdAgents<T>>::insert(adid, &agent, ());
+            <AgentAds<T>>::insert(&agent, adid, ());
+            Self::deposit_event(RawEvent::AgentAssigned(adid, agent));
+        }
+
+        #[weight = 0]
+        fn unassign_agent(origin, adid: AdIndex, agent: T::AccountId) {
+            let sender = ensure_signed(origin)?;
+            Self::check_ad_role(&sender,&adid,Role::CampaignManager)?;
+            if <AgentAds<T>>::contains_key(&agent, adid) {
+                <AdAgents<T>>::remove(adid, &agent);
+                <AgentAds<T>>::remove(&agent, adid);
+                <AgentAdsCount<T>>::mutate(&agent, |count| *count = count.saturating_sub(1));
+            }
+            Self::deposit_event(RawEvent::AgentUnassigned(adid, agent));
+        }
+
+        /// Slash up to `amount` of an agent's stake.
+        #[weight = 0]
+        fn slash_agent(origin, agent: T::AccountId, amount: T::Balance) {
+            T::ModeratorOrigin::ensure_origin(origin)?;
+            let mut info = Self::agents(&agent).ok_or(Error::<T>::AgentNotExists)?;
+            let amount = amount.min(info.stake);
+            let _ = <pallet_balances::Module<T>>::slash_reserved(&agent, amount);
+            info.stake = info.stake.saturating_sub(amount);
+            <Agents<T>>::insert(&agent, info);
+            Self::deposit_event(RawEvent::AgentSlashed(agent, amount));
+        }
+
+        /// Remove a misbehaving agent, returning what is left of its stake.
+        #[weight = <Module<T>>::clear_agent_ads_weight()]
+        fn remove_agent(origin, agent: T::AccountId) {
+            T::ModeratorOrigin::ensure_origin(origin)?;
+            Self::remove_agent_of(&agent)?;
+        }
+
+        /// Set the number of display slots auctioned from the next epoch on.
+        #[weight = 0]
+        fn set_slot_count(origin, count: u32) {
+            T::ModeratorOrigin::ensure_origin(origin)?;
+            ensure!(count <= MAX_SLOTS, Error::<T>::TooManySlots);
+            SlotCount::put(count);
+            Self::deposit_event(RawEvent::SlotCountChanged(count));
+        }
+
+        /// Add a member did to an org advertiser account, or change its role and
+        /// spend limit. Budget already spent by the member is kept. Only the org did
+        /// itself can make or change admins.
+        #[weight = 0]
+        fn set_member(origin, org: T::Hash, member: T::Hash, role: Role, spend_limit: Option<T::Balance>) {
+            let sender = ensure_signed(origin)?;
+            let from_key = Self::check_org_admin(&sender, &org)?;
+            ensure!(member != org, Error::<T>::InvalidMember);
+            ensure!(<did::IdentityOf<T>>::contains_key(member), <did::Error<T>>::DidNotExists);
+            if from_key != org {
+                let is_admin = Self::org_members(&org, &member).map_or(false, |info| info.role == Role::Admin);
+                ensure!(!is_admin && role != Role::Admin, Error::<T>::NoPermission);
+            }
+            <OrgMembers<T>>::insert(&org, &member, OrgMember { role: role.clone(), spend_limit });
+            Self::deposit_event(RawEvent::MemberSet(org, member, role, spend_limit));
+        }
+
+        #[weight = 0]
+        fn remove_member(origin, org: T::Hash, member: T::Hash) {
+            let sender = ensure_signed(origin)?;
+            let from_key = Self::check_org_admin(&sender, &org)?;
+            let info = Self::org_members(&org, &member).ok_or(Error::<T>::MemberNotExists)?;
+            ensure!(from_key == org || info.role != Role::Admin, Error::<T>::NoPermission);
+            <OrgMembers<T>>::remove(&org, &member);
+            Self::deposit_event(RawEvent::MemberRemoved(org, member));
+        }
 	}
 }
 impl<T: Trait> Module<T> {
+    /// Move ads stored before storage version 1 to the current layout. Ads published before
+    /// moderation existed keep running as approved ads. Returns the entries migrated.
+    fn migrate() -> u64 {
+        use frame_support::{Twox64Concat, migration::StorageKeyIterator};
+
+        // active ads were kept by position, a removal moved the last one into the gap
+        let mut actives: Vec<(u64, AdIndex)> =
+            StorageKeyIterator::<u64, AdIndex, Twox64Concat>::new(b"AdsModule", b"AdsActives").drain().collect();
+        actives.sort();
+        AdsActiveCount::kill();
+        for (_, adid) in actives.iter() {
+            AdsActiveList::append(adid);
+        }
+
+        <AdsRecords<T>>::translate(|_, old: OldAdsMetadata<T::Balance, T::Moment>| {
+            Some(AdsMetadata {
+                advertiser: old.advertiser,
+                topic: old.topic,
+                total_amount: old.total_amount,
+                spend_amount: old.spend_amount,
+                single_click_fee: old.single_click_fee,
+                display_page: old.display_page,
+                landing_page: old.landing_page,
+                create_time: old.create_time,
+                active: old.active.is_some(),
+                distribute_type: old.distribute_type,
+                targeting: Targeting::default(),
+                review: ReviewStatus::Approved,
+                pricing: PricingMode::Fixed,
+                fee_currency: FeeCurrency::PRM,
+            })
+        });
+        Self::all_ads_count() + actives.len() as u64
+    }
 
-    fn check_ad_owner(sender: &T::AccountId,adid:&AdIndex) ->DispatchResult{
+
+    /// Check that `sender` owns the ad, or is a member of the owning org with at
+    /// least `role`. Returns the did of the sender and of the owner.
+    fn check_ad_role(sender: &T::AccountId, adid: &AdIndex, role: Role) -> Result<(T::Hash, T::Hash), DispatchError> {
         let (from_key, _) = <did::Module<T>>::identity(sender).ok_or(<did::Error<T>>::DidNotExists)?;
         ensure!(<AdsRecords<T>>::contains_key(adid), Error::<T>::ADNotExists);
-        ensure!(<AdsOwner<T>>::get(adid) == from_key,Error::<T>::NotOwner);
+        let owner = <AdsOwner<T>>::get(adid);
+        if owner != from_key {
+            let member = Self::org_members(&owner, &from_key).ok_or(Error::<T>::NotOwner)?;
+            ensure!(member.role >= role, Error::<T>::NoPermission);
+        }
+        Ok((from_key, owner))
+    }
+
+    /// Check that `sender` is the org did itself or one of its admins. Returns
+    /// the did of the sender.
+    fn check_org_admin(sender: &T::AccountId, org: &T::Hash) -> Result<T::Hash, DispatchError> {
+        let (from_key, _) = <did::Module<T>>::identity(sender).ok_or(<did::Error<T>>::DidNotExists)?;
+        if from_key != *org {
+            let member = Self::org_members(org, &from_key).ok_or(Error::<T>::NotOwner)?;
+            ensure!(member.role == Role::Admin, Error::<T>::NoPermission);
+        }
+        Ok(from_key)
+    }
+
+    /// Count `amount` of org budget moved by `member` against its spend limit.
+    /// The owner itself is never limited.
+    fn note_member_spend(owner: &T::Hash, member: &T::Hash, amount: T::Balance) -> DispatchResult {
+        if owner == member {
+            return Ok(());
+        }
+        let info = Self::org_members(owner, member).ok_or(Error::<T>::MemberNotExists)?;
+        let spent = Self::member_spent(owner, member).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+        if let Some(limit) = info.spend_limit {
+            ensure!(spent <= limit, Error::<T>::SpendLimitExceeded);
+        }
+        <MemberSpent<T>>::insert(owner, member, spent);
         Ok(())
     }
 
@@ -261,9 +997,10 @@ impl<T: Trait> Module<T> {
     fn active_ad(adid: &AdIndex) -> DispatchResult {
         let mut ads_metadata = Self::ads_records(adid);
         debug::info!("{:?}",ads_metadata.advertiser);
-        ensure!(ads_metadata.active.is_none(),Error::<T>::Active);
-        AdsActiveList::add(adid);
-        ads_metadata.active = Some(AdsActiveList::size().checked_sub(1).ok_or(Error::<T>::Overflow)?);
+        ensure!(!ads_metadata.active,Error::<T>::Active);
+        AdsActiveList::append(adid);
+        ads_metadata.active = true;
+        Self::insert_topic_index(adid, &ads_metadata.targeting);
         <AdsRecords<T>>::insert(adid, ads_metadata);
         Ok(())
     }
@@ -272,24 +1009,229 @@ impl<T: Trait> Module<T> {
         ensure!(<AdsRecords<T>>::contains_key(adid),<did::Error<T>>::DidNotExists);
         let mut ads_metadata = Self::ads_records(adid);
         debug::info!("{:?}",ads_metadata.advertiser);
-        ensure!(ads_metadata.active.is_some(),Error::<T>::NotActive);
-        let index = ads_metadata.active.unwrap();
-        //get last active ad index
-        let last_index = AdsActiveList::size().checked_sub(1).ok_or(Error::<T>::Overflow)?;
-        if let Some(last_adid) = AdsActiveList::get(&last_index){
-            if last_adid!=*adid{
-                let mut last_ads_metadata = Self::ads_records(last_adid);
-                last_ads_metadata.active = Some(index.clone());
-                <AdsRecords<T>>::insert(last_adid,last_ads_metadata);    
-            }
-            AdsActiveList::remove(&index);
-            ads_metadata.active = None;
-            <AdsRecords<T>>::insert(adid,ads_metadata);
+        ensure!(ads_metadata.active,Error::<T>::NotActive);
+        AdsActiveList::remove(adid);
+        Self::remove_topic_index(adid, &ads_metadata.targeting);
+        ads_metadata.active = false;
+        <AdsRecords<T>>::insert(adid,ads_metadata);
+
+        Ok(())
+    }
+
+    /// Native amount paid per click of a fixed price ad, with the rate used
+    /// when the fee is priced in another currency.
+    fn native_click_fee(ads_metadata: &AdsMetadata<T::Balance, T::Moment>) -> Result<(T::Balance, Option<Price>), DispatchError> {
+        let fee = ads_metadata.single_click_fee;
+        if ads_metadata.fee_currency == FeeCurrency::PRM {
+            return Ok((fee, None));
+        }
+        let (rate, fed_at) = T::Prices::native_price(&ads_metadata.fee_currency).ok_or(Error::<T>::PriceUnavailable)?;
+        let now = <pallet_timestamp::Module<T>>::get();
+        ensure!(now.saturating_sub(fed_at) <= T::MaxPriceAge::get(), Error::<T>::PriceStale);
+        let value = rate.checked_mul_int(&fee).ok_or(Error::<T>::Overflow)?;
+        Ok((value, Some(rate)))
+    }
+
+    fn note_reward(adid: &AdIndex, user: &T::Hash) {
+        let rewarded = <AdUserRewards<T>>::get(adid, user);
+        if rewarded == 0 {
+            <AdUniqueUsers>::mutate(adid, |n| *n = n.saturating_add(1));
+        }
+        <AdUserRewards<T>>::insert(adid, user, rewarded.saturating_add(1));
+        <UserRewardCount<T>>::mutate(user, |n| *n = n.saturating_add(1));
+        <AdClicks>::mutate(adid, |n| *n = n.saturating_add(1));
+    }
+
+    /// Active ads in activation order, paginated by `offset` and `limit`.
+    pub fn active_ads(offset: u32, limit: u32) -> Vec<AdIndex> {
+        AdsActiveList::iter()
+            .skip(offset as usize)
+            .take(limit.min(MAX_QUERY_LIMIT) as usize)
+            .collect()
+    }
+
+    /// Budget left to pay out, `None` if the ad does not exist.
+    pub fn remaining_budget(adid: AdIndex) -> Option<T::Balance> {
+        if !<AdsRecords<T>>::contains_key(adid) {
+            return None;
+        }
+        let ads_metadata = Self::ads_records(adid);
+        Some(ads_metadata.total_amount.checked_sub(&ads_metadata.spend_amount).unwrap_or_else(Zero::zero))
+    }
+
+    /// Total amount already paid out by an ad.
+    pub fn rewards_paid(adid: AdIndex) -> T::Balance {
+        Self::ads_records(adid).spend_amount
+    }
+
+    pub fn campaign_stats(adid: AdIndex) -> Option<CampaignStats<T::Balance>> {
+        let remaining = Self::remaining_budget(adid)?;
+        let ads_metadata = Self::ads_records(adid);
+        Some(CampaignStats {
+            total_amount: ads_metadata.total_amount,
+            spend_amount: ads_metadata.spend_amount,
+            remaining,
+            single_click_fee: ads_metadata.single_click_fee,
+            clicks: Self::ad_clicks(adid),
+            unique_users: Self::ad_unique_users(adid),
+            active: ads_metadata.active,
+        })
+    }
+
+    fn check_targeting(targeting: &Targeting) -> DispatchResult {
+        ensure!(targeting.topics.len() <= MAX_TOPICS, Error::<T>::TooManyTargets);
+        ensure!(targeting.regions.len() <= MAX_TARGETS, Error::<T>::TooManyTargets);
+        ensure!(targeting.languages.len() <= MAX_TARGETS, Error::<T>::TooManyTargets);
+        Ok(())
+    }
+
+    fn insert_topic_index(adid: &AdIndex, targeting: &Targeting) {
+        for topic in targeting.topics.iter() {
+            <TopicAds>::insert(topic, adid, ());
+        }
+    }
+
+    fn remove_topic_index(adid: &AdIndex, targeting: &Targeting) {
+        for topic in targeting.topics.iter() {
+            <TopicAds>::remove(topic, adid);
+        }
+    }
+
+    fn matches(targeting: &Targeting, query: &TargetingQuery) -> bool {
+        if let Some(topic) = &query.topic {
+            if !targeting.topics.contains(topic) { return false; }
+        }
+        if let Some(region) = &query.region {
+            if !targeting.regions.is_empty() && !targeting.regions.contains(region) { return false; }
         }
+        if let Some(language) = &query.language {
+            if !targeting.languages.is_empty() && !targeting.languages.contains(language) { return false; }
+        }
+        if let (Some(wanted), Some(did_type)) = (&targeting.did_type, &query.did_type) {
+            if wanted != did_type { return false; }
+        }
+        true
+    }
+
+    /// Active ads matching `query`, paginated by `offset` and `limit`.
+    ///
+    /// When the query names a topic only that topic's index is scanned, otherwise
+    /// the whole active list is walked in order.
+    pub fn active_ads_by_targeting(query: TargetingQuery, offset: u32, limit: u32) -> Vec<AdIndex> {
+        let limit = limit.min(MAX_QUERY_LIMIT) as usize;
+        let candidates: Vec<AdIndex> = match query.topic {
+            Some(topic) => <TopicAds>::iter_prefix(topic).map(|(adid, _)| adid).collect(),
+            None => AdsActiveList::iter().collect(),
+        };
+        candidates.into_iter()
+            .filter(|adid| {
+                let ads_metadata = Self::ads_records(adid);
+                ads_metadata.active && Self::matches(&ads_metadata.targeting, &query)
+            })
+            .skip(offset as usize)
+            .take(limit)
+            .collect()
+    }
+
+    /// Drops the agent together with its ad assignments, so registering again
+    /// doesn't bring them back.
+    fn remove_agent_of(agent: &T::AccountId) -> DispatchResult {
+        let info = <Agents<T>>::take(agent).ok_or(Error::<T>::AgentNotExists)?;
+        Self::clear_agent_ads(agent);
+        <AgentUnbonding<T>>::remove(agent);
+        <pallet_balances::Module<T>>::unreserve(agent, info.stake);
+        Self::deposit_event(RawEvent::AgentUnregistered(agent.clone()));
+        Ok(())
+    }
+
+    /// Drop every ad assignment of an agent, at most `MaxAgentAds` of them.
+    fn clear_agent_ads(agent: &T::AccountId) {
+        for (adid, _) in <AgentAds<T>>::iter_prefix(agent) {
+            <AdAgents<T>>::remove(adid, agent);
+        }
+        <AgentAds<T>>::remove_prefix(agent);
+        <AgentAdsCount<T>>::remove(agent);
+    }
 
+    fn clear_agent_ads_weight() -> Weight {
+        let ads = T::MaxAgentAds::get() as Weight;
+        T::DbWeight::get().reads_writes(4 + ads, 5 + ads)
+    }
+
+    fn check_bid(adid: &AdIndex, amount: T::Balance) -> DispatchResult {
+        let ads_metadata = Self::ads_records(adid);
+        ensure!(ads_metadata.pricing == PricingMode::Auction, Error::<T>::NotAuctionMode);
+        ensure!(amount >= T::MinBid::get(), Error::<T>::BidTooLow);
+        let remaining = ads_metadata.total_amount.checked_sub(&ads_metadata.spend_amount).unwrap_or_else(Zero::zero);
+        ensure!(amount <= remaining, Error::<T>::BidExceedsBudget);
         Ok(())
     }
 
+    /// Count an ad bidding for the first time in `epoch` against `MaxBids`.
+    fn note_bidder(epoch: EpochIndex, adid: &AdIndex) -> DispatchResult {
+        if <Bids<T>>::contains_key(epoch, adid) || <SealedBids<T>>::contains_key(epoch, adid) {
+            return Ok(());
+        }
+        let count = Self::bid_count();
+        ensure!(count < T::MaxBids::get(), Error::<T>::TooManyBids);
+        BidCount::put(count + 1);
+        Ok(())
+    }
+
+    fn in_reveal_period() -> bool {
+        let epoch_length = T::EpochLength::get();
+        if epoch_length.is_zero() {
+            return false;
+        }
+        let position = <frame_system::Module<T>>::block_number() % epoch_length;
+        position >= epoch_length.saturating_sub(T::RevealPeriod::get())
+    }
+
+    /// Clear the auction of the current epoch and open the next one.
+    ///
+    /// Bids of active, auction mode ads are ranked highest first (earlier ads win
+    /// ties). Each of the top `SlotCount` bidders pays the next bid below it, or
+    /// `MinBid` when there is none. Unrevealed sealed bids are dropped.
+    fn clear_epoch() -> Weight {
+        let epoch = Self::current_epoch();
+        let previous = Self::slot_winners();
+        for (adid, _) in previous.iter() {
+            <SlotPrice<T>>::remove(adid);
+        }
+
+        let mut bids: Vec<(AdIndex, T::Balance)> = <Bids<T>>::iter_prefix(epoch)
+            .filter(|(adid, amount)| {
+                let ads_metadata = Self::ads_records(adid);
+                let remaining = ads_metadata.total_amount.checked_sub(&ads_metadata.spend_amount).unwrap_or_else(Zero::zero);
+                ads_metadata.active && ads_metadata.pricing == PricingMode::Auction && *amount <= remaining
+            })
+            .collect();
+        // every bid and the ad behind it are read, at most `MaxBids` of them
+        let bidders = Self::bid_count() as Weight;
+        bids.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let slots = Self::slot_count() as usize;
+        let winners: Vec<(AdIndex, T::Balance)> = (0..bids.len().min(slots))
+            .map(|i| {
+                let price = bids.get(i + 1).map(|next| next.1).unwrap_or_else(T::MinBid::get);
+                (bids[i].0, price)
+            })
+            .collect();
+        for (adid, price) in winners.iter() {
+            <SlotPrice<T>>::insert(adid, price);
+            Self::deposit_event(RawEvent::SlotWon(epoch, *adid, *price));
+        }
+        let slot_writes = (previous.len() + winners.len()) as Weight;
+        <SlotWinners<T>>::put(winners);
+
+        <Bids<T>>::remove_prefix(epoch);
+        <SealedBids<T>>::remove_prefix(epoch);
+        BidCount::kill();
+        CurrentEpoch::put(epoch.wrapping_add(1));
+        Self::deposit_event(RawEvent::EpochCleared(epoch));
+        T::DbWeight::get().reads_writes(4 + 2 * bidders, 5 + slot_writes + bidders)
+    }
+
     fn is_sub(mut haystack: &[u8], needle: &[u8]) -> bool {
         if needle.len() == 0 { return true; }
         while !haystack.is_empty() {
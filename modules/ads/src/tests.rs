@@ -3,7 +3,7 @@
 use super::*;
 
 use frame_support::{assert_ok, assert_noop, impl_outer_origin, parameter_types,
-  weights::Weight,};
+  traits::{OnInitialize, OnRuntimeUpgrade}, weights::{Weight, constants::RocksDbWeight}, migration::put_storage_value, StorageHasher, Twox64Concat,};
 use sp_core::H256;
 // The testing primitives are very useful for avoiding having to work with signatures
 // or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
@@ -40,7 +40,7 @@ impl frame_system::Trait for Test {
 	type Event = ();
 	type BlockHashCount = BlockHashCount;
 	type MaximumBlockWeight = MaximumBlockWeight;
-	type DbWeight = ();
+	type DbWeight = RocksDbWeight;
 	type BlockExecutionWeight = ();
 	type ExtrinsicBaseWeight = ();
 	type MaximumBlockLength = MaximumBlockLength;
@@ -84,8 +84,53 @@ impl did::Trait for Test {
   type Event = ();
 }
 
+parameter_types! {
+  pub const ReportBond: u64 = 10;
+  pub const ReportSlash: u64 = 100;
+  pub const EpochLength: u64 = 10;
+  pub const RevealPeriod: u64 = 3;
+  pub const MinBid: u64 = 1;
+  pub const MaxBids: u32 = 3;
+  pub const AgentStake: u64 = 100;
+  pub const AgentUnbondPeriod: u64 = 10;
+  pub const MaxAgentAds: u32 = 3;
+  pub const MaxAgentFee: Permill = Permill::from_percent(20);
+  pub const MaxPriceAge: u64 = 1000;
+}
+
+thread_local! {
+  static USD_PRICE: std::cell::RefCell<Option<(Price, u64)>> = std::cell::RefCell::new(None);
+}
+
+pub struct TestPrices;
+impl PriceProvider<u64> for TestPrices {
+  fn native_price(currency: &FeeCurrency) -> Option<(Price, u64)> {
+    match currency {
+      FeeCurrency::USD => USD_PRICE.with(|p| *p.borrow()),
+      FeeCurrency::PRM => Some((Price::from_natural(1), 0)),
+    }
+  }
+}
+
+fn set_usd_price(price: Option<(Price, u64)>) {
+  USD_PRICE.with(|p| *p.borrow_mut() = price);
+}
+
 impl Trait for Test {
   type Event = ();
+  type ModeratorOrigin = frame_system::EnsureRoot<u64>;
+  type ReportBond = ReportBond;
+  type ReportSlash = ReportSlash;
+  type EpochLength = EpochLength;
+  type RevealPeriod = RevealPeriod;
+  type MinBid = MinBid;
+  type MaxBids = MaxBids;
+  type AgentStake = AgentStake;
+  type AgentUnbondPeriod = AgentUnbondPeriod;
+  type MaxAgentAds = MaxAgentAds;
+  type MaxAgentFee = MaxAgentFee;
+  type Prices = TestPrices;
+  type MaxPriceAge = MaxPriceAge;
 }
 
 type AdsModule = Module<Test>;
@@ -102,6 +147,7 @@ fn new_test_ext() -> sp_io::TestExternalities {
       (1, 10000),
       (2, 10000),
       (3, 10000),
+      (4, 10000),
     ],
   }.assimilate_storage(&mut t).unwrap();
 
@@ -110,6 +156,7 @@ fn new_test_ext() -> sp_io::TestExternalities {
     min_deposit: 50,
     base_quota: 250,
     fee_to_previous: 25,
+    dids: vec![],
   }.assimilate_storage(&mut t).unwrap();
 
   GenesisConfig::<Test> {
@@ -151,10 +198,35 @@ fn prepare_dids_for_test() {
     Origin::signed(1),
     b"0x5e9c79234b5e55348fc60f38b28c2cc60d8bb4bd2862eae2179a05ec39e62658".to_vec(),
     3u64,
+    "4".as_bytes().to_vec(),
+    DidModule::identity(1).unwrap().0,
+    None,
+    None
+  ));
+
+  // ad viewer
+  assert_ok!(DidModule::create(
+    Origin::signed(1),
+    b"0x8eaf04151687736326c9fea17e25fc5287613693c912909cb226aa4794f26a48".to_vec(),
+    4u64,
     "1".as_bytes().to_vec(),
     H256::zero(),
-    Some("n".as_bytes().to_vec()),
-    Some("s".as_bytes().to_vec())
+    Some("v".as_bytes().to_vec()),
+    Some("f".as_bytes().to_vec())
+  ));
+}
+
+fn publish_ad(total_amount: u64, single_click_fee: u64) {
+  assert_ok!(AdsModule::publish(
+    Origin::signed(3),
+    b"huawei".to_vec(),
+    b"p20 pro".to_vec(),
+    total_amount,
+    single_click_fee,
+    b"display".to_vec(),
+    None,
+    DistributeType::AGENT,
+    Targeting::default()
   ));
 }
 
@@ -164,28 +236,28 @@ fn should_pass_publish() {
     prepare_dids_for_test();
 
     // publish ads
-    assert_ok!(AdsModule::publish(
-      Origin::signed(3),
-      b"huawei".to_vec(),
-      b"p20 pro".to_vec(),
-      1000,
-      1,
-      30
-    ));
+    publish_ad(1000, 1);
 
     assert_eq!(Balances::free_balance(&3), 9000);
     assert_eq!(Balances::free_balance(&2), 10000);
 
     let (user_key, _) = DidModule::identity(3).unwrap();
-    assert_eq!(AdsModule::ads_records(user_key), AdsMetadata {
+    assert_eq!(AdsModule::owned_ads(user_key), vec![0]);
+    assert_eq!(AdsModule::ads_records(0), AdsMetadata {
       advertiser: b"huawei".to_vec(),
       topic: b"p20 pro".to_vec(),
       total_amount: 1000,
-      surplus: 1000,
-      gas_fee_used: 0,
+      spend_amount: 0,
       single_click_fee: 1,
+      display_page: b"display".to_vec(),
+      landing_page: None,
       create_time: 0,
-      period: 30,
+      active: false,
+      distribute_type: DistributeType::AGENT,
+      targeting: Targeting::default(),
+      review: ReviewStatus::Pending,
+      pricing: PricingMode::Fixed,
+      fee_currency: FeeCurrency::PRM,
     });
 
   });
@@ -203,8 +275,11 @@ fn less_than_min_deposit_should_not_pass() {
       b"p20 pro".to_vec(),
       100,
       1,
-      30
-    ), "min deposit 500 prm");
+      b"display".to_vec(),
+      None,
+      DistributeType::AGENT,
+      Targeting::default()
+    ), Error::<Test>::MineDeposit);
 
     assert_eq!(Balances::free_balance(&3), 10000);
     assert_eq!(Balances::free_balance(&2), 9000);
@@ -218,23 +293,18 @@ fn should_pass_deposit() {
     prepare_dids_for_test();
 
     // publish ads
-    assert_ok!(AdsModule::publish(
-      Origin::signed(3),
-      b"huawei".to_vec(),
-      b"p20 pro".to_vec(),
-      1000,
-      1,
-      30
-    ));
+    publish_ad(1000, 1);
 
     assert_ok!(AdsModule::deposit(
       Origin::signed(3),
+      0,
       500,
       b"new deposit".to_vec()
     ));
 
     assert_eq!(Balances::free_balance(&3), 8500);
     assert_eq!(Balances::free_balance(&2), 10500);
+    assert_eq!(AdsModule::ads_records(0).total_amount, 1500);
 
   });
 }
@@ -245,23 +315,26 @@ fn should_pass_withdraw() {
     prepare_dids_for_test();
 
     // publish ads
-    assert_ok!(AdsModule::publish(
+    publish_ad(1000, 1);
+
+    assert_noop!(AdsModule::withdraw(
       Origin::signed(3),
-      b"huawei".to_vec(),
-      b"p20 pro".to_vec(),
-      1000,
-      1,
-      30
-    ));
+      0,
+      200,
+      b"withdraw money".to_vec()
+    ), Error::<Test>::TimeNotReach);
 
+    pallet_timestamp::Module::<Test>::set_timestamp(30 * 24 * 60 * 60 * 1000);
     assert_ok!(AdsModule::withdraw(
       Origin::signed(3),
+      0,
       200,
       b"withdraw money".to_vec()
     ));
 
     assert_eq!(Balances::free_balance(&3), 9200);
     assert_eq!(Balances::free_balance(&2), 9800);
+    assert_eq!(AdsModule::ads_records(0).total_amount, 800);
 
   });
 }
@@ -272,58 +345,582 @@ fn should_pass_distribute() {
     prepare_dids_for_test();
 
     // publish ads
+    publish_ad(1000, 200);
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::zero()));
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 0, 1));
+
+    let (user, _) = DidModule::identity(4).unwrap();
+    assert_ok!(AdsModule::distribute(
+      Origin::signed(1),
+      0,
+      user
+    ));
+
+    assert_eq!(Balances::free_balance(&3), 9000);
+    assert_eq!(Balances::free_balance(&2), 9800);
+    assert_eq!(Balances::free_balance(&4), 10200);
+    assert_eq!(AdsModule::ads_records(0).spend_amount, 200);
+
+  });
+}
+
+#[test]
+fn with_no_permission_should_not_pass_distribute() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    // publish ads
+    publish_ad(1000, 200);
+
+    let (user, _) = DidModule::identity(4).unwrap();
+    assert_noop!(AdsModule::distribute(
+      Origin::signed(2),
+      0,
+      user
+    ), Error::<Test>::NotActive);
+
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    assert_noop!(AdsModule::distribute(
+      Origin::signed(3),
+      0,
+      user
+    ), Error::<Test>::NeedAgentAccountSigned);
+
+    assert_eq!(Balances::free_balance(&3), 9000);
+    assert_eq!(Balances::free_balance(&2), 10000);
+    assert_eq!(Balances::free_balance(&4), 10000);
+  });
+}
+
+#[test]
+fn should_index_active_ads_by_topic() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    let targeting = Targeting {
+      topics: vec![7, 9],
+      regions: vec![b"CN".to_vec()],
+      languages: vec![],
+      did_type: None,
+    };
     assert_ok!(AdsModule::publish(
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
       1000,
       1,
-      30
+      b"display".to_vec(),
+      None,
+      DistributeType::AGENT,
+      targeting
     ));
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
 
-    let (publisher, _) = DidModule::identity(3).unwrap();
-    let (user, _) = DidModule::identity(1).unwrap();
-    assert_ok!(AdsModule::distribute(
-      Origin::signed(2),
-      publisher,
-      user,
-      200
+    let by_topic = TargetingQuery { topic: Some(9), ..Default::default() };
+    assert_eq!(AdsModule::active_ads_by_targeting(by_topic.clone(), 0, 10), vec![0]);
+    assert_eq!(AdsModule::active_ads_by_targeting(by_topic.clone(), 1, 10), Vec::<AdIndex>::new());
+
+    let other_region = TargetingQuery { region: Some(b"US".to_vec()), ..Default::default() };
+    assert_eq!(AdsModule::active_ads_by_targeting(other_region, 0, 10), Vec::<AdIndex>::new());
+
+    assert_ok!(AdsModule::pause(Origin::signed(3), 0));
+    assert!(!<TopicAds>::contains_key(9, 0));
+    assert_eq!(AdsModule::active_ads_by_targeting(by_topic, 0, 10), Vec::<AdIndex>::new());
+  });
+}
+
+#[test]
+fn pausing_should_keep_active_ads_in_order() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    for adid in 0..4 {
+      publish_ad(1000, 1);
+      assert_ok!(AdsModule::approve(Origin::ROOT, adid));
+    }
+    assert_eq!(AdsModule::active_ads(0, 10), vec![0, 1, 2, 3]);
+
+    assert_ok!(AdsModule::pause(Origin::signed(3), 1));
+    assert_eq!(AdsModule::active_ads(0, 10), vec![0, 2, 3]);
+    assert_eq!(AdsModule::active_ads(1, 1), vec![2]);
+    assert_noop!(AdsModule::pause(Origin::signed(3), 1), Error::<Test>::NotActive);
+
+    assert_ok!(AdsModule::active(Origin::signed(3), 1));
+    assert_eq!(AdsModule::active_ads(0, 10), vec![0, 2, 3, 1]);
+    assert_eq!(AdsModule::ads_active_count(), 4);
+  });
+}
+
+#[test]
+fn should_track_campaign_stats() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      1000,
+      10,
+      b"display".to_vec(),
+      None,
+      DistributeType::AGENT,
+      Targeting::default()
     ));
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::zero()));
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 0, 1));
+
+    let (user, _) = DidModule::identity(4).unwrap();
+    assert_ok!(AdsModule::distribute(Origin::signed(1), 0, user));
+    assert_ok!(AdsModule::distribute(Origin::signed(1), 0, user));
+
+    assert_eq!(AdsModule::active_ads(0, 10), vec![0]);
+    assert_eq!(AdsModule::remaining_budget(0), Some(980));
+    assert_eq!(AdsModule::rewards_paid(0), 20);
+    assert_eq!(AdsModule::ad_user_rewards(0, user), 2);
+    assert_eq!(AdsModule::user_reward_count(user), 2);
+    assert_eq!(AdsModule::campaign_stats(0), Some(CampaignStats {
+      total_amount: 1000,
+      spend_amount: 20,
+      remaining: 980,
+      single_click_fee: 10,
+      clicks: 2,
+      unique_users: 1,
+      active: true,
+    }));
+    assert_eq!(AdsModule::campaign_stats(1), None);
+  });
+}
 
-    assert_eq!(Balances::free_balance(&3), 9000);
-    assert_eq!(Balances::free_balance(&2), 9800);
-    assert_eq!(Balances::free_balance(&1), 10225);
+#[test]
+fn should_moderate_ads_and_creatives() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
+      Origin::signed(3),
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      1000,
+      10,
+      b"display".to_vec(),
+      None,
+      DistributeType::AGENT,
+      Targeting::default()
+    ));
+
+    // pending ads can not go live
+    assert_noop!(AdsModule::active(Origin::signed(3), 0), Error::<Test>::NotApproved);
+    assert_noop!(AdsModule::approve(Origin::signed(3), 0), sp_runtime::traits::BadOrigin);
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    assert!(AdsModule::ads_records(0).active);
 
+    // swapping the landing page waits for review
+    assert_ok!(AdsModule::update_ads(
+      Origin::signed(3), 0, None, None, None, Some(b"https://evil".to_vec()), None
+    ));
+    assert_eq!(AdsModule::ads_records(0).landing_page, None);
+    assert!(AdsModule::pending_creatives(0).is_some());
+    assert_ok!(AdsModule::reject(Origin::ROOT, 0, b"phishing".to_vec()));
+    assert!(AdsModule::pending_creatives(0).is_none());
+    assert_eq!(AdsModule::ads_records(0).landing_page, None);
+    assert_eq!(AdsModule::ads_records(0).review, ReviewStatus::Approved);
   });
 }
 
 #[test]
-fn with_no_permission_should_not_pass_distribute() {
+fn upheld_report_should_pause_and_slash() {
   new_test_ext().execute_with(|| {
     prepare_dids_for_test();
 
-    // publish ads
     assert_ok!(AdsModule::publish(
       Origin::signed(3),
       b"huawei".to_vec(),
       b"p20 pro".to_vec(),
       1000,
-      1,
-      30
+      10,
+      b"display".to_vec(),
+      None,
+      DistributeType::AGENT,
+      Targeting::default()
     ));
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+
+    assert_ok!(AdsModule::report(Origin::signed(1), 0, b"scam".to_vec()));
+    assert_noop!(AdsModule::report(Origin::signed(1), 0, b"scam".to_vec()), Error::<Test>::AlreadyReported);
+    assert_eq!(Balances::reserved_balance(&1), 10);
+
+    let before = Balances::free_balance(&1);
+    assert_ok!(AdsModule::resolve_report(Origin::ROOT, 0, 1, true));
+    assert_eq!(Balances::reserved_balance(&1), 0);
+    assert_eq!(Balances::free_balance(&1), before + 10 + 100);
+    assert!(!AdsModule::ads_records(0).active);
+    assert_eq!(AdsModule::ads_records(0).total_amount, 900);
+    assert_eq!(AdsModule::ads_records(0).review, ReviewStatus::Pending);
+    assert_noop!(AdsModule::active(Origin::signed(3), 0), Error::<Test>::NotApproved);
+
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    assert!(AdsModule::ads_records(0).active);
+  });
+}
 
-    let (publisher, _) = DidModule::identity(3).unwrap();
-    let (user, _) = DidModule::identity(1).unwrap();
-    assert_noop!(AdsModule::distribute(
+#[test]
+fn should_clear_slot_auction_at_second_price() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    for _ in 0..3 {
+      assert_ok!(AdsModule::publish(
+        Origin::signed(3),
+        b"huawei".to_vec(),
+        b"p20 pro".to_vec(),
+        1000,
+        10,
+        b"display".to_vec(),
+        None,
+        DistributeType::AGENT,
+        Targeting::default()
+      ));
+    }
+    for adid in 0..3 {
+      assert_ok!(AdsModule::approve(Origin::ROOT, adid));
+    }
+    assert_noop!(AdsModule::bid(Origin::signed(3), 0, 5), Error::<Test>::NotAuctionMode);
+    for adid in 0..3 {
+      assert_ok!(AdsModule::set_pricing_mode(Origin::signed(3), adid, PricingMode::Auction));
+    }
+    assert_ok!(AdsModule::set_slot_count(Origin::ROOT, 2));
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::zero()));
+    for adid in 0..3 {
+      assert_ok!(AdsModule::assign_agent(Origin::signed(3), adid, 1));
+    }
+
+    assert_noop!(AdsModule::bid(Origin::signed(3), 0, 0), Error::<Test>::BidTooLow);
+    assert_noop!(AdsModule::bid(Origin::signed(3), 0, 2000), Error::<Test>::BidExceedsBudget);
+    assert_ok!(AdsModule::bid(Origin::signed(3), 0, 5));
+    assert_ok!(AdsModule::bid(Origin::signed(3), 1, 8));
+
+    // sealed bid for ad 2
+    let salt = b"salt".to_vec();
+    let commitment = BlakeTwo256::hash_of(&(2u64, 3u64, salt.clone()));
+    assert_ok!(AdsModule::commit_bid(Origin::signed(3), 2, commitment));
+    assert_noop!(AdsModule::reveal_bid(Origin::signed(3), 2, 3, salt.clone()), Error::<Test>::NotRevealPeriod);
+    frame_system::Module::<Test>::set_block_number(8);
+    assert_noop!(AdsModule::reveal_bid(Origin::signed(3), 2, 4, salt.clone()), Error::<Test>::InvalidReveal);
+    assert_ok!(AdsModule::reveal_bid(Origin::signed(3), 2, 3, salt));
+
+    // auction ads can not be paid before they win a slot
+    let (user, _) = DidModule::identity(4).unwrap();
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 1, user), Error::<Test>::NoSlot);
+
+    AdsModule::on_initialize(10);
+    assert_eq!(AdsModule::current_epoch(), 1);
+    assert_eq!(AdsModule::slot_winners(), vec![(1, 5), (0, 3)]);
+    assert_eq!(AdsModule::bids(0, 1), None);
+
+    assert_ok!(AdsModule::distribute(Origin::signed(1), 1, user));
+    assert_eq!(AdsModule::rewards_paid(1), 5);
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 2, user), Error::<Test>::NoSlot);
+
+    // no bids in the next epoch frees the slots
+    AdsModule::on_initialize(20);
+    assert_eq!(AdsModule::slot_winners(), vec![]);
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 1, user), Error::<Test>::NoSlot);
+  });
+}
+
+#[test]
+fn bids_should_be_capped_per_epoch() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    for adid in 0..4 {
+      publish_ad(500, 10);
+      assert_ok!(AdsModule::approve(Origin::ROOT, adid));
+      assert_ok!(AdsModule::set_pricing_mode(Origin::signed(3), adid, PricingMode::Auction));
+    }
+    assert_ok!(AdsModule::bid(Origin::signed(3), 0, 5));
+    assert_ok!(AdsModule::bid(Origin::signed(3), 1, 5));
+    assert_ok!(AdsModule::commit_bid(Origin::signed(3), 2, H256::repeat_byte(1)));
+    assert_eq!(AdsModule::bid_count(), 3);
+    assert_noop!(AdsModule::bid(Origin::signed(3), 3, 5), Error::<Test>::TooManyBids);
+    assert_noop!(AdsModule::commit_bid(Origin::signed(3), 3, H256::repeat_byte(1)), Error::<Test>::TooManyBids);
+    // raising a bid doesn't take another place
+    assert_ok!(AdsModule::bid(Origin::signed(3), 0, 6));
+
+    // open bids would be seen by the sealed bidders revealing theirs
+    frame_system::Module::<Test>::set_block_number(8);
+    assert_noop!(AdsModule::bid(Origin::signed(3), 0, 7), Error::<Test>::CommitPeriodOver);
+
+    let weight = AdsModule::on_initialize(10);
+    assert!(weight > AdsModule::on_initialize(11));
+    assert_eq!(AdsModule::bid_count(), 0);
+    frame_system::Module::<Test>::set_block_number(11);
+    assert_ok!(AdsModule::bid(Origin::signed(3), 3, 5));
+  });
+}
+
+#[test]
+fn assigned_agents_should_distribute_and_take_fee() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    assert_ok!(AdsModule::publish(
       Origin::signed(3),
-      publisher,
-      user,
-      200
-    ), "you have no access to use the funds");
+      b"huawei".to_vec(),
+      b"p20 pro".to_vec(),
+      1000,
+      100,
+      b"display".to_vec(),
+      None,
+      DistributeType::AGENT,
+      Targeting::default()
+    ));
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    let (user, _) = DidModule::identity(4).unwrap();
+
+    assert_noop!(AdsModule::register_agent(Origin::signed(1), Permill::from_percent(30)), Error::<Test>::AgentFeeTooHigh);
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::from_percent(10)));
+    assert_noop!(AdsModule::register_agent(Origin::signed(1), Permill::zero()), Error::<Test>::AlreadyAgent);
+    assert_eq!(Balances::reserved_balance(&1), 100);
+
+    // registered but not assigned by the advertiser
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 0, user), Error::<Test>::NeedAgentAccountSigned);
+    assert_noop!(AdsModule::assign_agent(Origin::signed(1), 0, 1), Error::<Test>::NotOwner);
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 0, 1));
+
+    // agent 1 keeps a 10% fee, the viewer gets the rest
+    let contract_before = Balances::free_balance(&2);
+    assert_ok!(AdsModule::distribute(Origin::signed(1), 0, user));
+    assert_eq!(Balances::free_balance(&2), contract_before - 100);
+    assert_eq!(Balances::free_balance(&4), 10090);
+    assert_eq!(AdsModule::rewards_paid(0), 100);
+
+    assert_ok!(AdsModule::slash_agent(Origin::ROOT, 1, 40));
+    assert_eq!(AdsModule::agents(1).unwrap().stake, 60);
+    assert_eq!(Balances::reserved_balance(&1), 60);
+
+    assert_ok!(AdsModule::remove_agent(Origin::ROOT, 1));
+    assert_eq!(Balances::reserved_balance(&1), 0);
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 0, user), Error::<Test>::NeedAgentAccountSigned);
+  });
+}
 
-    assert_eq!(Balances::free_balance(&3), 9000);
-    assert_eq!(Balances::free_balance(&2), 10000);
-    assert_eq!(Balances::free_balance(&1), 10025);
+#[test]
+fn removed_agents_should_lose_their_assignments() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    publish_ad(1000, 10);
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::zero()));
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 0, 1));
+    let (user, _) = DidModule::identity(4).unwrap();
+
+    assert_ok!(AdsModule::slash_agent(Origin::ROOT, 1, 100));
+    assert_ok!(AdsModule::remove_agent(Origin::ROOT, 1));
+    assert!(!<AdAgents<Test>>::contains_key(0, 1));
+    assert!(!<AgentAds<Test>>::contains_key(1, 0));
+
+    // a fresh registration starts without the old assignments
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::zero()));
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 0, user), Error::<Test>::NeedAgentAccountSigned);
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 0, 1));
+    assert_ok!(AdsModule::distribute(Origin::signed(1), 0, user));
+  });
+}
+
+#[test]
+fn unregistering_agents_should_stay_slashable() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    for adid in 0..4 {
+      publish_ad(500, 10);
+      assert_ok!(AdsModule::approve(Origin::ROOT, adid));
+    }
+    let (user, _) = DidModule::identity(4).unwrap();
+
+    // the contract did pays the fees, it can't be an agent itself
+    assert_noop!(AdsModule::register_agent(Origin::signed(2), Permill::zero()), Error::<Test>::InvalidAgent);
+    let before = Balances::free_balance(&1);
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::zero()));
+    for adid in 0..3 {
+      assert_ok!(AdsModule::assign_agent(Origin::signed(3), adid, 1));
+    }
+    assert_noop!(AdsModule::assign_agent(Origin::signed(3), 3, 1), Error::<Test>::TooManyAgentAds);
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 0, 1));
+    assert_ok!(AdsModule::unassign_agent(Origin::signed(3), 0, 1));
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 3, 1));
+    assert_eq!(AdsModule::agent_ads_count(1), 3);
+
+    let now = frame_system::Module::<Test>::block_number();
+    assert_ok!(AdsModule::unregister_agent(Origin::signed(1)));
+    assert_eq!(AdsModule::agent_unbonding(1), Some(now + 10));
+    assert_eq!(AdsModule::agent_ads_count(1), 0);
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 1, user), Error::<Test>::NeedAgentAccountSigned);
+    assert_noop!(AdsModule::assign_agent(Origin::signed(3), 1, 1), Error::<Test>::AgentUnbonding);
+    assert_noop!(AdsModule::withdraw_agent_stake(Origin::signed(1)), Error::<Test>::StillUnbonding);
+
+    // misbehaviour found while unbonding is still slashed
+    assert_ok!(AdsModule::slash_agent(Origin::ROOT, 1, 40));
+    frame_system::Module::<Test>::set_block_number(now + 10);
+    assert_ok!(AdsModule::withdraw_agent_stake(Origin::signed(1)));
+    assert_eq!(Balances::reserved_balance(&1), 0);
+    assert_eq!(Balances::free_balance(&1), before - 40);
+    assert!(AdsModule::agents(1).is_none());
+  });
+}
+
+#[test]
+fn usd_click_fee_should_convert_at_fed_rate() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+
+    publish_ad(1000, 10);
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+    assert_ok!(AdsModule::register_agent(Origin::signed(1), Permill::zero()));
+    assert_ok!(AdsModule::assign_agent(Origin::signed(3), 0, 1));
+    assert_ok!(AdsModule::set_fee_currency(Origin::signed(3), 0, FeeCurrency::USD));
+    let (user, _) = DidModule::identity(4).unwrap();
+
+    set_usd_price(None);
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 0, user), Error::<Test>::PriceUnavailable);
+
+    // 1 USD = 2.5 PRM, fed at 500
+    set_usd_price(Some((Price::from_rational(5u64, 2u64), 500)));
+    pallet_timestamp::Module::<Test>::set_timestamp(1600);
+    assert_noop!(AdsModule::distribute(Origin::signed(1), 0, user), Error::<Test>::PriceStale);
+
+    pallet_timestamp::Module::<Test>::set_timestamp(1500);
+    assert_ok!(AdsModule::distribute(Origin::signed(1), 0, user));
+    assert_eq!(AdsModule::rewards_paid(0), 25);
+    assert_eq!(Balances::free_balance(&4), 10025);
+  });
+}
 
+#[test]
+fn org_members_should_act_within_their_role() {
+  new_test_ext().execute_with(|| {
+    prepare_dids_for_test();
+    let org = DidModule::identity(3).unwrap().0;
+    let manager = DidModule::identity(4).unwrap().0;
+    let admin = DidModule::identity(1).unwrap().0;
+
+    publish_ad(1000, 1);
+    assert_ok!(AdsModule::approve(Origin::ROOT, 0));
+
+    // outsiders can neither run the ad nor manage the org
+    assert_noop!(AdsModule::pause(Origin::signed(4), 0), Error::<Test>::NotOwner);
+    assert_noop!(AdsModule::set_member(Origin::signed(4), org, manager, Role::Admin, None), Error::<Test>::NotOwner);
+    assert_noop!(AdsModule::set_member(Origin::signed(3), org, org, Role::Admin, None), Error::<Test>::InvalidMember);
+
+    assert_ok!(AdsModule::set_member(Origin::signed(3), org, manager, Role::Viewer, None));
+    assert_noop!(AdsModule::pause(Origin::signed(4), 0), Error::<Test>::NoPermission);
+
+    assert_ok!(AdsModule::set_member(Origin::signed(3), org, manager, Role::CampaignManager, Some(300)));
+    assert_ok!(AdsModule::pause(Origin::signed(4), 0));
+    assert_ok!(AdsModule::active(Origin::signed(4), 0));
+    assert_ok!(AdsModule::update_ads(Origin::signed(4), 0, Some(b"mate 30".to_vec()), None, None, None, None));
+    assert_noop!(AdsModule::set_member(Origin::signed(4), org, admin, Role::Admin, None), Error::<Test>::NoPermission);
+
+    // withdrawn budget goes back to the org and counts against the member limit
+    pallet_timestamp::Module::<Test>::set_timestamp(30 * 24 * 60 * 60 * 1000);
+    assert_ok!(AdsModule::withdraw(Origin::signed(4), 0, 200, b"withdraw money".to_vec()));
+    assert_eq!(Balances::free_balance(&3), 9200);
+    assert_eq!(AdsModule::member_spent(org, manager), 200);
+    assert_noop!(
+      AdsModule::withdraw(Origin::signed(4), 0, 200, b"withdraw money".to_vec()),
+      Error::<Test>::SpendLimitExceeded
+    );
+    assert_ok!(AdsModule::withdraw(Origin::signed(3), 0, 200, b"withdraw money".to_vec()));
+
+    // admins manage members on behalf of the org
+    assert_ok!(AdsModule::set_member(Origin::signed(3), org, admin, Role::Admin, None));
+    assert_ok!(AdsModule::set_member(Origin::signed(1), org, manager, Role::CampaignManager, Some(500)));
+    assert_eq!(AdsModule::member_spent(org, manager), 200);
+    assert_ok!(AdsModule::remove_member(Origin::signed(1), org, manager));
+    assert_noop!(AdsModule::pause(Origin::signed(4), 0), Error::<Test>::NotOwner);
+    assert_noop!(AdsModule::remove_member(Origin::signed(1), org, manager), Error::<Test>::MemberNotExists);
+
+    // removing and adding a member again doesn't reset what it spent
+    assert_ok!(AdsModule::set_member(Origin::signed(1), org, manager, Role::CampaignManager, Some(300)));
+    assert_eq!(AdsModule::member_spent(org, manager), 200);
+    assert_noop!(
+      AdsModule::withdraw(Origin::signed(4), 0, 200, b"withdraw money".to_vec()),
+      Error::<Test>::SpendLimitExceeded
+    );
+
+    // admins can't raise their own limits or touch other admins
+    assert_noop!(AdsModule::set_member(Origin::signed(1), org, admin, Role::Admin, Some(1000)), Error::<Test>::NoPermission);
+    assert_noop!(AdsModule::set_member(Origin::signed(1), org, manager, Role::Admin, None), Error::<Test>::NoPermission);
+    assert_noop!(AdsModule::remove_member(Origin::signed(1), org, admin), Error::<Test>::NoPermission);
+    assert_ok!(AdsModule::set_member(Origin::signed(3), org, admin, Role::Admin, Some(1000)));
+    assert_ok!(AdsModule::remove_member(Origin::signed(3), org, admin));
   });
-}
\ No newline at end of file
+}
+
+fn put_old_ad(adid: AdIndex, active: Option<u64>) {
+  let old = OldAdsMetadata::<u64, u64> {
+    advertiser: b"huawei".to_vec(),
+    topic: b"p20 pro".to_vec(),
+    total_amount: 1000,
+    spend_amount: 10,
+    single_click_fee: 1,
+    display_page: b"display".to_vec(),
+    landing_page: None,
+    create_time: 5,
+    active,
+    distribute_type: DistributeType::ADVERTISER,
+  };
+  put_storage_value(b"AdsModule", b"AdsRecords", &Twox64Concat::hash(&adid.encode()), old);
+}
+
+#[test]
+fn old_ads_should_migrate_on_runtime_upgrade() {
+  new_test_ext().execute_with(|| {
+    assert_eq!(AdsModule::storage_version(), STORAGE_VERSION);
+    StorageVersion::put(0);
+    put_old_ad(0, None);
+    put_old_ad(1, Some(1));
+    put_old_ad(2, Some(0));
+    AllAdsCount::put(3);
+    // ad 2 took the place of a removed ad
+    for (index, adid) in vec![(0u64, 2u64), (1, 1)] {
+      put_storage_value(b"AdsModule", b"AdsActives", &Twox64Concat::hash(&index.encode()), adid);
+    }
+    AdsActiveCount::put(2);
+
+    assert!(AdsModule::on_runtime_upgrade() > 0);
+    assert_eq!(AdsModule::storage_version(), STORAGE_VERSION);
+    let migrated = AdsModule::ads_records(1);
+    assert_eq!(migrated, AdsMetadata {
+      advertiser: b"huawei".to_vec(),
+      topic: b"p20 pro".to_vec(),
+      total_amount: 1000,
+      spend_amount: 10,
+      single_click_fee: 1,
+      display_page: b"display".to_vec(),
+      landing_page: None,
+      create_time: 5,
+      active: true,
+      distribute_type: DistributeType::ADVERTISER,
+      targeting: Targeting::default(),
+      review: ReviewStatus::Approved,
+      pricing: PricingMode::Fixed,
+      fee_currency: FeeCurrency::PRM,
+    });
+    assert!(!AdsModule::ads_records(0).active);
+    assert_eq!(AdsModule::active_ads(0, 10), vec![2, 1]);
+    assert_eq!(AdsModule::ads_active_count(), 2);
+    assert!(AdsActiveList::remove(&2));
+    assert!(AdsActiveList::append(&0));
+    assert_eq!(AdsModule::active_ads(0, 10), vec![1, 0]);
+
+    // runs once
+    AdsModule::on_runtime_upgrade();
+    assert_eq!(AdsModule::ads_records(1), migrated);
+  });
+}
@@ -0,0 +1,17 @@
+//! Runtime API definition for the did module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+
+sp_api::decl_runtime_apis! {
+	pub trait DidApi<Hash, Balance> where
+		Hash: Codec,
+		Balance: Codec,
+	{
+		/// Number of ads shares received by a did hash.
+		fn ads_share_count(did: Hash) -> u64;
+		/// Total ads income of a did hash as user and as superior.
+		fn ads_income(did: Hash) -> (Balance, Balance);
+	}
+}
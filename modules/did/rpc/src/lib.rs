@@ -0,0 +1,75 @@
+//! RPC interface for the did module.
+
+use std::sync::Arc;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use did_runtime_api::DidApi as DidRuntimeApi;
+
+#[rpc]
+pub trait DidApi<BlockHash, Hash, Balance> {
+	#[rpc(name = "did_adsShareCount")]
+	fn ads_share_count(&self, did: Hash, at: Option<BlockHash>) -> Result<u64>;
+
+	#[rpc(name = "did_adsIncome")]
+	fn ads_income(&self, did: Hash, at: Option<BlockHash>) -> Result<(Balance, Balance)>;
+}
+
+/// A struct that implements the [`DidApi`].
+pub struct Did<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Did<C, B> {
+	/// Create new `Did` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Did { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error<E: std::fmt::Debug>(message: &str, e: E) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, Hash, Balance> DidApi<<Block as BlockT>::Hash, Hash, Balance> for Did<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: DidRuntimeApi<Block, Hash, Balance>,
+	Hash: Codec,
+	Balance: Codec,
+{
+	fn ads_share_count(&self, did: Hash, at: Option<<Block as BlockT>::Hash>) -> Result<u64> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.ads_share_count(&at, did).map_err(|e| runtime_error("Unable to query ads share count.", e))
+	}
+
+	fn ads_income(&self, did: Hash, at: Option<<Block as BlockT>::Hash>) -> Result<(Balance, Balance)> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.ads_income(&at, did).map_err(|e| runtime_error("Unable to query ads income.", e))
+	}
+}
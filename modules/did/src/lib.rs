@@ -12,8 +12,8 @@ use frame_support::{
 	traits::{Currency, ReservableCurrency, ExistenceRequirement},
 };
 use sp_runtime::{
-	RuntimeDebug, DispatchResult, Permill,
-	traits::{Zero, CheckedSub, CheckedAdd, CheckedDiv, CheckedMul, Hash, SaturatedConversion,}
+	RuntimeDebug, DispatchResult, DispatchError, Permill,
+	traits::{Zero, CheckedSub, CheckedAdd, CheckedDiv, CheckedMul, Hash, SaturatedConversion, Saturating}
 };
 use frame_system::{self as system, ensure_root, ensure_signed};
 use sp_io::hashing::blake2_256;
@@ -131,6 +131,27 @@ decl_storage! {
 		pub AllDidCount get(fn all_did_count): u64;
 		pub UserKeys get(fn key_by_index): map hasher(twox_64_concat) T::Hash => T::Hash;
 		pub DidIndices get(fn index_by_key) : map hasher(twox_64_concat) T::Hash => Vec<u8>;
+
+		/// number of ads shares received by a did, each one recorded by an
+		/// `AdsUserShare` or `AdsSuperiorShare` event
+		pub AdsShareCount get(fn ads_share_count): map hasher(twox_64_concat) T::Hash => u64;
+		/// total ads income of a did as user and as superior
+		pub AdsIncome get(fn ads_income): map hasher(twox_64_concat) T::Hash => (T::Balance, T::Balance);
+	}
+	add_extra_genesis {
+		/// dids created at genesis as (address, pubkey, did type), with the
+		/// genesis account's did as superior once it exists
+		config(dids): Vec<(T::AccountId, Vec<u8>, Vec<u8>)>;
+		build(|config: &GenesisConfig<T>| {
+			for (address, pubkey, did_type) in config.dids.iter() {
+				let superior = <Module<T>>::identity(&config.genesis_account)
+					.map(|(key, _)| key)
+					.unwrap_or_default();
+				let did = <Module<T>>::generate_did(pubkey, did_type);
+				<Module<T>>::insert_did(config.genesis_account.clone(), address.clone(), did, superior, None)
+					.expect("genesis dids must be unique");
+			}
+		});
 	}
 }
 
@@ -146,6 +167,10 @@ decl_event! {
 			Locked(Did, Balance, Moment, Moment, u64, u64),
 			Unlocked(Did, Balance, Moment),
 			Transfered(Did, Did, Balance, Vec<u8>),
+			/// from, user, amount kept by the user, percent paid to the superior
+			AdsUserShare(Did, Did, Balance, u64),
+			/// from, superior, user, amount paid to the superior, percent paid to the superior
+			AdsSuperiorShare(Did, Did, Did, Balance, u64),
 			AddressAdded(Did, Vec<u8>, Vec<u8>),
 			GroupNameSet(Did, Vec<u8>),
     }
@@ -215,49 +240,7 @@ decl_module! {
 				}
 			}
 
-			if social_account_hash.is_some() {
-				let social_hash = social_account_hash.unwrap();
-				<SocialAccount<T>>::insert(social_hash, &user_key);
-			}
-
-			// update metadata
-			let metadata = MetadataRecord {
-				address: address.clone(),
-				superior: superior_key,
-				creator: sender.clone(),
-				did: did.clone(),
-				locked_records: None,
-				social_account: social_account_hash,
-				unlocked_records: None,
-				donate: None,
-				subordinate_count: 0,
-				group_name: None,
-				external_address: ExternalAddress {
-					btc: Vec::new(),
-					eth: Vec::new(),
-					eos: Vec::new(),
-				},
-			};
-			<Metadata<T>>::insert(&user_key, metadata);
-
-			// update address => did
-			<Identity<T>>::insert(&address, (&user_key, &did));
-
-			// update user_key => address
-			<IdentityOf<T>>::insert(&user_key, &address);
-
-			// update did count
-			let all_did_count = Self::all_did_count();
-			let new_count = all_did_count.checked_add(1)
-					.ok_or(Error::<T>::Overflow)?;
-			<AllDidCount>::put(new_count);
-
-			let harsher = HarshBuilder::new().salt("prochain did").length(6).init().unwrap();
-			let idx = harsher.encode(&[all_did_count]).unwrap();
-			let idx_hash = T::Hashing::hash(&idx);
-
-			<UserKeys<T>>::insert(&idx_hash, &user_key);
-			<DidIndices<T>>::insert(&user_key, idx);
+			Self::insert_did(sender, address, did.clone(), superior_key, social_account_hash)?;
 
 			// broadcast event
 			Self::deposit_event(RawEvent::Created(did, pubkey, superior_did));
@@ -596,6 +579,59 @@ impl<T: Trait> Module<T> {
 		false
 	}
 
+	/// Store a new did record and its indices, returning the user key.
+	fn insert_did(creator: T::AccountId, address: T::AccountId, did: Did, superior_key: T::Hash, social_account_hash: Option<T::Hash>) -> Result<T::Hash, DispatchError> {
+		let user_key = T::Hashing::hash(&did);
+		ensure!(!<Metadata<T>>::contains_key(&user_key), Error::<T>::DidExists);
+		ensure!(!<Identity<T>>::contains_key(&address), Error::<T>::DidExists);
+
+		if social_account_hash.is_some() {
+			let social_hash = social_account_hash.unwrap();
+			<SocialAccount<T>>::insert(social_hash, &user_key);
+		}
+
+		// update metadata
+		let metadata = MetadataRecord {
+			address: address.clone(),
+			superior: superior_key,
+			creator,
+			did: did.clone(),
+			locked_records: None,
+			social_account: social_account_hash,
+			unlocked_records: None,
+			donate: None,
+			subordinate_count: 0,
+			group_name: None,
+			external_address: ExternalAddress {
+				btc: Vec::new(),
+				eth: Vec::new(),
+				eos: Vec::new(),
+			},
+		};
+		<Metadata<T>>::insert(&user_key, metadata);
+
+		// update address => did
+		<Identity<T>>::insert(&address, (&user_key, &did));
+
+		// update user_key => address
+		<IdentityOf<T>>::insert(&user_key, &address);
+
+		// update did count
+		let all_did_count = Self::all_did_count();
+		let new_count = all_did_count.checked_add(1)
+				.ok_or(Error::<T>::Overflow)?;
+		<AllDidCount>::put(new_count);
+
+		let harsher = HarshBuilder::new().salt("prochain did").length(6).init().unwrap();
+		let idx = harsher.encode(&[all_did_count]).unwrap();
+		let idx_hash = T::Hashing::hash(&idx);
+
+		<UserKeys<T>>::insert(&idx_hash, &user_key);
+		<DidIndices<T>>::insert(&user_key, idx);
+
+		Ok(user_key)
+	}
+
 	fn generate_did(pubkey: &[u8], did_type: &[u8]) -> Vec<u8> {
 		// 通过公钥生成hash值
 		let mut hash = blake2_256(pubkey);
@@ -644,10 +680,18 @@ impl<T: Trait> Module<T> {
 			let rewards_ratio = if locked_records.is_some() { locked_records.unwrap().rewards_ratio } else { 0 };
 
 			let fee_to_superior = value.clone() * Self::u128_to_balance(rewards_ratio.into()) / Self::u128_to_balance(100);
-			let fee_to_user = value.clone() * Self::u128_to_balance((100 - rewards_ratio).into()) / Self::u128_to_balance(100);
+			// the user keeps the rounding remainder so both shares add up to `value`
+			let fee_to_user = value.checked_sub(&fee_to_superior).ok_or(Error::<T>::Overflow)?;
 
 			<pallet_balances::Module<T> as Currency<_>>::transfer(&from_address, &superior_address, fee_to_superior, ExistenceRequirement::AllowDeath)?;
 			<pallet_balances::Module<T> as Currency<_>>::transfer(&from_address, &to_address, fee_to_user, ExistenceRequirement::AllowDeath)?;
+
+			Self::note_ads_share(&to_user, fee_to_user, false);
+			Self::note_ads_share(&superior, fee_to_superior, true);
+
+			let MetadataRecord { did: superior_did, .. } = Self::metadata(&superior);
+			Self::deposit_event(RawEvent::AdsUserShare(from_did.clone(), to_did.clone(), fee_to_user, rewards_ratio));
+			Self::deposit_event(RawEvent::AdsSuperiorShare(from_did.clone(), superior_did, to_did.clone(), fee_to_superior, rewards_ratio));
 		} else {
 			<pallet_balances::Module<T> as Currency<_>>::transfer(&from_address, &to_address, value, ExistenceRequirement::AllowDeath)?;
 		}
@@ -656,4 +700,16 @@ impl<T: Trait> Module<T> {
 
 		Ok(())
 	}
+
+	/// Add an ads share to the running totals of `receiver`.
+	fn note_ads_share(receiver: &T::Hash, amount: T::Balance, as_superior: bool) {
+		<AdsIncome<T>>::mutate(receiver, |(user_income, superior_income)| {
+			if as_superior {
+				*superior_income = superior_income.saturating_add(amount);
+			} else {
+				*user_income = user_income.saturating_add(amount);
+			}
+		});
+		<AdsShareCount<T>>::mutate(receiver, |count| *count = count.saturating_add(1));
+	}
 }
@@ -130,6 +130,7 @@ fn new_test_ext() -> sp_io::TestExternalities {
     min_deposit: 10,
     base_quota: 250,
     fee_to_previous: 25,
+    dids: vec![],
   }.assimilate_storage(&mut t).unwrap();
 
   t.into()
@@ -463,6 +464,49 @@ fn should_pass_transfer() {
   });
 }
 
+#[test]
+fn ads_split_should_record_each_share() {
+  new_test_ext().execute_with(|| {
+    System::set_block_number(1);
+
+    prepare_dids_for_test();
+
+    let (_, from_did) = DidModule::identity(&1).unwrap();
+    let (superior_key, superior_did) = DidModule::identity(&2).unwrap();
+    let (user_key, user_did) = DidModule::identity(&3).unwrap();
+    assert_ok!(DidModule::transfer(
+      Origin::signed(1),
+      user_key,
+      999,
+      b"ads fee".to_vec()
+    ));
+
+    // the rounding remainder stays with the user
+    assert_eq!(Balances::free_balance(&3), 10799);
+    assert_eq!(Balances::free_balance(&2), 9099);
+
+    let events: Vec<_> = System::events().into_iter().map(|r| r.event).collect();
+    assert!(events.contains(&TestEvent::did(RawEvent::AdsUserShare(from_did.clone(), user_did.clone(), 800, 20))));
+    assert!(events.contains(&TestEvent::did(RawEvent::AdsSuperiorShare(from_did, superior_did, user_did, 199, 20))));
+
+    assert_eq!(DidModule::ads_income(user_key), (800, 0));
+    assert_eq!(DidModule::ads_income(superior_key), (0, 199));
+    assert_eq!(DidModule::ads_share_count(user_key), 1);
+    assert_eq!(DidModule::ads_share_count(superior_key), 1);
+
+    // further clicks only move the totals
+    assert_ok!(DidModule::transfer(Origin::signed(1), user_key, 100, b"ads fee".to_vec()));
+    assert_eq!(DidModule::ads_income(user_key), (880, 0));
+    assert_eq!(DidModule::ads_income(superior_key), (0, 219));
+    assert_eq!(DidModule::ads_share_count(user_key), 2);
+
+    // plain transfers are not ads income
+    assert_ok!(DidModule::transfer(Origin::signed(1), user_key, 100, b"transfer".to_vec()));
+    assert_eq!(DidModule::ads_share_count(user_key), 2);
+    assert_eq!(DidModule::ads_income(user_key), (880, 0));
+  });
+}
+
 #[test]
 fn should_not_pass_transfer() {
   new_test_ext().execute_with(|| {
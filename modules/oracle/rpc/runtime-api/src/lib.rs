@@ -0,0 +1,31 @@
+//! Runtime API definition for the oracle module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::Codec;
+pub use oracle::{EventLogSource, Reconciliation, ScanConfig, SwapInfo, SwapTotals};
+
+sp_api::decl_runtime_apis! {
+	pub trait OracleSwapApi<AccountId, Hash, Balance, BlockNumber> where
+		AccountId: Codec,
+		Hash: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// A swap with its state and pending release.
+		fn swap(swap_id: Hash) -> Option<SwapInfo<AccountId, Hash, Balance, BlockNumber>>;
+
+		/// Open swaps of both directions, oldest first, following the swap `after`.
+		fn open_swaps(after: Option<Hash>, limit: u32) -> Vec<SwapInfo<AccountId, Hash, Balance, BlockNumber>>;
+
+		/// Ethereum swaps paying the given did hash.
+		fn swaps_by_receiver(did: Hash, offset: u32, limit: u32) -> Vec<SwapInfo<AccountId, Hash, Balance, BlockNumber>>;
+
+		/// Swap totals in both directions against the balance of the lock account.
+		fn reconciliation() -> Reconciliation<Balance>;
+
+		/// Event source and scan config of the offchain worker job.
+		fn scan_job() -> Option<(EventLogSource, Option<ScanConfig>)>;
+	}
+}
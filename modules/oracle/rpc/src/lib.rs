@@ -0,0 +1,107 @@
+//! RPC interface for the oracle module.
+
+use std::sync::Arc;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use oracle_runtime_api::{
+	OracleSwapApi as OracleSwapRuntimeApi, EventLogSource, Reconciliation, ScanConfig, SwapInfo, SwapTotals,
+};
+
+#[rpc]
+pub trait OracleSwapApi<BlockHash, AccountId, Hash, Balance, BlockNumber> {
+	#[rpc(name = "swap_getSwap")]
+	fn swap(&self, swap_id: Hash, at: Option<BlockHash>) -> Result<Option<SwapInfo<AccountId, Hash, Balance, BlockNumber>>>;
+
+	#[rpc(name = "swap_openSwaps")]
+	fn open_swaps(&self, after: Option<Hash>, limit: u32, at: Option<BlockHash>) -> Result<Vec<SwapInfo<AccountId, Hash, Balance, BlockNumber>>>;
+
+	#[rpc(name = "swap_swapsByReceiver")]
+	fn swaps_by_receiver(&self, did: Hash, offset: u32, limit: u32, at: Option<BlockHash>) -> Result<Vec<SwapInfo<AccountId, Hash, Balance, BlockNumber>>>;
+
+	#[rpc(name = "swap_totals")]
+	fn totals(&self, at: Option<BlockHash>) -> Result<Reconciliation<Balance>>;
+
+	#[rpc(name = "swap_scanJob")]
+	fn scan_job(&self, at: Option<BlockHash>) -> Result<Option<(EventLogSource, Option<ScanConfig>)>>;
+}
+
+/// A struct that implements the [`OracleSwapApi`].
+pub struct OracleSwap<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> OracleSwap<C, B> {
+	/// Create new `OracleSwap` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		OracleSwap { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error<E: std::fmt::Debug>(message: &str, e: E) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, Hash, Balance, BlockNumber> OracleSwapApi<<Block as BlockT>::Hash, AccountId, Hash, Balance, BlockNumber>
+	for OracleSwap<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: OracleSwapRuntimeApi<Block, AccountId, Hash, Balance, BlockNumber>,
+	AccountId: Codec,
+	Hash: Codec,
+	Balance: Codec,
+	BlockNumber: Codec,
+{
+	fn swap(&self, swap_id: Hash, at: Option<<Block as BlockT>::Hash>) -> Result<Option<SwapInfo<AccountId, Hash, Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.swap(&at, swap_id).map_err(|e| runtime_error("Unable to query swap.", e))
+	}
+
+	fn open_swaps(&self, after: Option<Hash>, limit: u32, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<SwapInfo<AccountId, Hash, Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.open_swaps(&at, after, limit).map_err(|e| runtime_error("Unable to query open swaps.", e))
+	}
+
+	fn swaps_by_receiver(&self, did: Hash, offset: u32, limit: u32, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<SwapInfo<AccountId, Hash, Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.swaps_by_receiver(&at, did, offset, limit).map_err(|e| runtime_error("Unable to query receiver swaps.", e))
+	}
+
+	fn totals(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Reconciliation<Balance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.reconciliation(&at).map_err(|e| runtime_error("Unable to query swap totals.", e))
+	}
+
+	fn scan_job(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Option<(EventLogSource, Option<ScanConfig>)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.scan_job(&at).map_err(|e| runtime_error("Unable to query scan job.", e))
+	}
+}
@@ -0,0 +1,474 @@
+//! Decoding of ethereum logs as returned by `eth_getLogs` from etherscan or a json rpc node,
+//! and verification of logs against block headers with receipt trie proofs.
+
+use hex::FromHex;
+use simple_json::{self, json::JsonValue};
+use sp_std::{convert::TryInto, prelude::*, vec::Vec};
+
+const KEY_STATUS: &'static str = "status";
+const KEY_MESSAGE: &'static str = "message";
+const KEY_RESULT: &'static str = "result";
+const KEY_ERROR: &'static str = "error";
+const KEY_ADDRESS: &'static str = "address";
+const KEY_TOPICS: &'static str = "topics";
+const KEY_DATA: &'static str = "data";
+const KEY_BLOCK_NUMBER: &'static str = "blockNumber";
+const KEY_TX_HASH: &'static str = "transactionHash";
+const KEY_REMOVED: &'static str = "removed";
+
+const STATUS_OK: &'static [u8] = b"1";
+const MESSAGE_NOT_FOUND: &'static [u8] = b"No records found";
+const HEX_PREFIX: &'static [u8] = b"0x";
+
+/// A 32 byte abi word or topic.
+pub type Word = [u8; 32];
+
+/// An ethereum event log.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Log {
+	pub address: [u8; 20],
+	pub topics: Vec<Word>,
+	pub data: Vec<u8>,
+	pub block_number: u64,
+	/// Zero for logs decoded from a receipt.
+	pub transaction_hash: Word,
+	/// Dropped by a chain reorganisation, only reported by json rpc nodes.
+	pub removed: bool,
+}
+
+impl Log {
+	/// Whether the log was emitted by the event with the given signature topic.
+	pub fn is_event(&self, signature: &Word) -> bool {
+		self.topics.first() == Some(signature)
+	}
+}
+
+/// Abi encoded event data read one word at a time.
+pub struct AbiData<'a>(&'a [u8]);
+
+impl<'a> AbiData<'a> {
+	pub fn new(data: &'a [u8]) -> Self {
+		AbiData(data)
+	}
+
+	/// The word at `index`.
+	pub fn word(&self, index: usize) -> Result<&'a [u8], &'static str> {
+		let start = index.checked_mul(32).ok_or("error abi word index overflow")?;
+		self.slice(start, 32)
+	}
+
+	pub fn bytes32(&self, index: usize) -> Result<Word, &'static str> {
+		let mut word = [0u8; 32];
+		word.copy_from_slice(self.word(index)?);
+		Ok(word)
+	}
+
+	/// A `uint256` at `index` which must fit in 128 bits.
+	pub fn uint(&self, index: usize) -> Result<u128, &'static str> {
+		let word = self.word(index)?;
+		if word[..16].iter().any(|b| *b != 0) {
+			return Err("error abi uint overflow");
+		}
+		let mut low = [0u8; 16];
+		low.copy_from_slice(&word[16..]);
+		Ok(u128::from_be_bytes(low))
+	}
+
+	pub fn address(&self, index: usize) -> Result<[u8; 20], &'static str> {
+		let word = self.word(index)?;
+		if word[..12].iter().any(|b| *b != 0) {
+			return Err("error abi address has dirty bytes");
+		}
+		let mut address = [0u8; 20];
+		address.copy_from_slice(&word[12..]);
+		Ok(address)
+	}
+
+	/// Dynamic `bytes` or `string` whose offset is stored in the word at `index`.
+	pub fn bytes(&self, index: usize) -> Result<&'a [u8], &'static str> {
+		let offset = self.usize_at(index.checked_mul(32).ok_or("error abi word index overflow")?)?;
+		let len = self.usize_at(offset)?;
+		self.slice(offset.checked_add(32).ok_or("error abi offset overflow")?, len)
+	}
+
+	fn usize_at(&self, start: usize) -> Result<usize, &'static str> {
+		let word = self.slice(start, 32)?;
+		if word[..24].iter().any(|b| *b != 0) {
+			return Err("error abi offset overflow");
+		}
+		let mut low = [0u8; 8];
+		low.copy_from_slice(&word[24..]);
+		u64::from_be_bytes(low).try_into().map_err(|_| "error abi offset overflow")
+	}
+
+	fn slice(&self, start: usize, len: usize) -> Result<&'a [u8], &'static str> {
+		let end = start.checked_add(len).ok_or("error abi data too short")?;
+		self.0.get(start..end).ok_or("error abi data too short")
+	}
+}
+
+/// Logs in the `result` of an `eth_getLogs` response.
+pub fn parse_logs_response(body: &[u8]) -> Result<Vec<Log>, &'static str> {
+	let object = parse_response(body)?;
+	match field(&object, KEY_RESULT) {
+		Some(JsonValue::Array(logs)) => logs.iter().map(parse_log).collect(),
+		_ => Err("error response without result logs"),
+	}
+}
+
+/// Quantity in the `result` of a response, e.g. of `eth_blockNumber`.
+pub fn parse_quantity_response(body: &[u8]) -> Result<u64, &'static str> {
+	let object = parse_response(body)?;
+	let result = field(&object, KEY_RESULT).and_then(string).ok_or("error response without result")?;
+	parse_quantity(&result)
+}
+
+/// Hex bytes with an optional `0x` prefix.
+pub fn decode_hex(hex: &[u8]) -> Result<Vec<u8>, &'static str> {
+	let hex = if hex.starts_with(HEX_PREFIX) { &hex[HEX_PREFIX.len()..] } else { hex };
+	Vec::from_hex(hex).map_err(|_| "error invalid hex")
+}
+
+pub fn decode_word(hex: &[u8]) -> Result<Word, &'static str> {
+	let bytes = decode_hex(hex)?;
+	if bytes.len() != 32 {
+		return Err("error word is not 32 bytes");
+	}
+	let mut word = [0u8; 32];
+	word.copy_from_slice(&bytes);
+	Ok(word)
+}
+
+/// Lower case hex without prefix.
+pub fn encode_hex(bytes: &[u8]) -> Vec<u8> {
+	const DIGITS: &'static [u8; 16] = b"0123456789abcdef";
+	let mut hex = Vec::with_capacity(bytes.len() * 2);
+	for b in bytes {
+		hex.push(DIGITS[(b >> 4) as usize]);
+		hex.push(DIGITS[(b & 0xf) as usize]);
+	}
+	hex
+}
+
+/// Json rpc quantity, e.g. `0x1f`.
+pub fn parse_quantity(quantity: &[u8]) -> Result<u64, &'static str> {
+	if !quantity.starts_with(HEX_PREFIX) || quantity.len() == HEX_PREFIX.len() {
+		return Err("error invalid quantity");
+	}
+	let digits = core::str::from_utf8(&quantity[HEX_PREFIX.len()..]).map_err(|_| "error invalid quantity")?;
+	u64::from_str_radix(digits, 16).map_err(|_| "error invalid quantity")
+}
+
+/// Top level object of a response, rejecting provider errors.
+fn parse_response(body: &[u8]) -> Result<Vec<(Vec<char>, JsonValue)>, &'static str> {
+	let json_str = core::str::from_utf8(body).map_err(|_| "error response is not valid utf8")?;
+	let object = match simple_json::parse_json(json_str).map_err(|_| "error parse response json")? {
+		JsonValue::Object(object) => object,
+		_ => return Err("error response is not an object"),
+	};
+
+	// etherscan reports "No records found" as a failed status
+	if let Some(status) = field(&object, KEY_STATUS).and_then(string) {
+		let message = field(&object, KEY_MESSAGE).and_then(string).unwrap_or_default();
+		if status != STATUS_OK && message != MESSAGE_NOT_FOUND {
+			return Err("error etherscan request failed");
+		}
+	}
+	if field(&object, KEY_ERROR).is_some() {
+		return Err("error json rpc request failed");
+	}
+	Ok(object)
+}
+
+fn parse_log(value: &JsonValue) -> Result<Log, &'static str> {
+	let object = match value {
+		JsonValue::Object(object) => object,
+		_ => return Err("error log is not an object"),
+	};
+
+	let address = decode_hex(&string_field(object, KEY_ADDRESS)?)?;
+	if address.len() != 20 {
+		return Err("error log address is not 20 bytes");
+	}
+	let mut log_address = [0u8; 20];
+	log_address.copy_from_slice(&address);
+
+	let topics = match field(object, KEY_TOPICS) {
+		Some(JsonValue::Array(topics)) => topics
+			.iter()
+			.map(|topic| string(topic).ok_or("error log topic is not a string").and_then(|t| decode_word(&t)))
+			.collect::<Result<Vec<_>, _>>()?,
+		_ => return Err("error log without topics"),
+	};
+
+	let removed = match field(object, KEY_REMOVED) {
+		Some(JsonValue::Boolean(removed)) => *removed,
+		_ => false,
+	};
+
+	Ok(Log {
+		address: log_address,
+		topics,
+		data: decode_hex(&string_field(object, KEY_DATA)?)?,
+		block_number: parse_quantity(&string_field(object, KEY_BLOCK_NUMBER)?)?,
+		transaction_hash: decode_word(&string_field(object, KEY_TX_HASH)?)?,
+		removed,
+	})
+}
+
+fn field<'a>(object: &'a [(Vec<char>, JsonValue)], name: &str) -> Option<&'a JsonValue> {
+	object.iter().find(|(key, _)| key.iter().copied().eq(name.chars())).map(|(_, value)| value)
+}
+
+fn string(value: &JsonValue) -> Option<Vec<u8>> {
+	match value {
+		JsonValue::String(chars) => Some(chars.iter().map(|c| *c as u8).collect()),
+		_ => None,
+	}
+}
+
+fn string_field(object: &[(Vec<char>, JsonValue)], name: &'static str) -> Result<Vec<u8>, &'static str> {
+	field(object, name).and_then(string).ok_or("error log field missing")
+}
+
+/// Fields of an ethereum block header used to verify receipts.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Header {
+	pub hash: Word,
+	pub parent_hash: Word,
+	pub receipts_root: Word,
+	pub difficulty: u128,
+	pub number: u64,
+	pub timestamp: u64,
+}
+
+/// Decode an rlp encoded block header.
+pub fn decode_header(rlp: &[u8]) -> Result<Header, &'static str> {
+	let items = rlp_list(rlp)?;
+	if items.len() < 15 {
+		return Err("error header fields missing");
+	}
+	Ok(Header {
+		hash: sp_io::hashing::keccak_256(rlp),
+		parent_hash: rlp_word(items[0])?,
+		receipts_root: rlp_word(items[5])?,
+		difficulty: rlp_uint(items[7])?,
+		number: rlp_uint(items[8])?.try_into().map_err(|_| "error header number overflow")?,
+		timestamp: rlp_uint(items[11])?.try_into().map_err(|_| "error header timestamp overflow")?,
+	})
+}
+
+/// Logs of a legacy or typed receipt taken from a receipts trie.
+pub fn decode_receipt_logs(receipt: &[u8], block_number: u64) -> Result<Vec<Log>, &'static str> {
+	// typed receipts are prefixed by their transaction type
+	let receipt = match receipt.first() {
+		Some(tx_type) if *tx_type <= 0x7f => &receipt[1..],
+		_ => receipt,
+	};
+	let fields = rlp_list(receipt)?;
+	if fields.len() != 4 {
+		return Err("error receipt fields mismatch");
+	}
+
+	rlp_list(fields[3])?
+		.into_iter()
+		.map(|log| {
+			let fields = rlp_list(log)?;
+			if fields.len() != 3 {
+				return Err("error log fields mismatch");
+			}
+			let address = rlp_bytes(fields[0])?;
+			if address.len() != 20 {
+				return Err("error log address is not 20 bytes");
+			}
+			let mut log_address = [0u8; 20];
+			log_address.copy_from_slice(address);
+			Ok(Log {
+				address: log_address,
+				topics: rlp_list(fields[1])?.into_iter().map(rlp_word).collect::<Result<Vec<_>, _>>()?,
+				data: rlp_bytes(fields[2])?.to_vec(),
+				block_number,
+				transaction_hash: [0u8; 32],
+				removed: false,
+			})
+		})
+		.collect()
+}
+
+/// Key of a transaction index in the transactions and receipts tries.
+pub fn rlp_index(index: u64) -> Vec<u8> {
+	let bytes = index.to_be_bytes();
+	let bytes = &bytes[bytes.iter().take_while(|b| **b == 0).count()..];
+	match bytes {
+		[] => vec![0x80],
+		[b] if *b < 0x80 => vec![*b],
+		_ => {
+			let mut key = vec![0x80 + bytes.len() as u8];
+			key.extend_from_slice(bytes);
+			key
+		},
+	}
+}
+
+enum NodeRef<'a> {
+	Hash(Word),
+	Inline(&'a [u8]),
+}
+
+/// Value stored at `key` in the merkle patricia trie with the given root, proven by the
+/// nodes on the path from the root.
+pub fn verify_proof(root: &Word, key: &[u8], proof: &[Vec<u8>]) -> Result<Vec<u8>, &'static str> {
+	let nibbles: Vec<u8> = key.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect();
+	let mut pos = 0;
+	let mut proof = proof.iter();
+	let mut next = NodeRef::Hash(*root);
+
+	loop {
+		let node: &[u8] = match next {
+			NodeRef::Hash(hash) => {
+				let node = proof.next().ok_or("error proof too short")?;
+				if sp_io::hashing::keccak_256(node) != hash {
+					return Err("error proof node hash mismatch");
+				}
+				node
+			},
+			NodeRef::Inline(node) => node,
+		};
+
+		let items = rlp_list(node)?;
+		match items.len() {
+			17 => {
+				if pos == nibbles.len() {
+					let value = rlp_bytes(items[16])?;
+					if value.is_empty() {
+						return Err("error key not in trie");
+					}
+					return Ok(value.to_vec());
+				}
+				next = node_ref(items[nibbles[pos] as usize])?;
+				pos += 1;
+			},
+			2 => {
+				let (path, is_leaf) = decode_compact(rlp_bytes(items[0])?)?;
+				if !nibbles[pos..].starts_with(&path) {
+					return Err("error key not in trie");
+				}
+				pos += path.len();
+				if is_leaf {
+					if pos != nibbles.len() {
+						return Err("error key not in trie");
+					}
+					return Ok(rlp_bytes(items[1])?.to_vec());
+				}
+				next = node_ref(items[1])?;
+			},
+			_ => return Err("error invalid trie node"),
+		}
+	}
+}
+
+fn node_ref(item: &[u8]) -> Result<NodeRef<'_>, &'static str> {
+	let (payload, is_list, _) = rlp_item(item)?;
+	if is_list {
+		return Ok(NodeRef::Inline(item));
+	}
+	match payload.len() {
+		0 => Err("error key not in trie"),
+		32 => {
+			let mut hash = [0u8; 32];
+			hash.copy_from_slice(payload);
+			Ok(NodeRef::Hash(hash))
+		},
+		_ => Err("error invalid trie node reference"),
+	}
+}
+
+/// Nibbles of a hex prefix encoded path and whether it ends in a leaf.
+fn decode_compact(path: &[u8]) -> Result<(Vec<u8>, bool), &'static str> {
+	let first = *path.first().ok_or("error empty trie path")?;
+	let flag = first >> 4;
+	if flag > 3 {
+		return Err("error invalid trie path");
+	}
+	let mut nibbles = Vec::with_capacity(path.len() * 2);
+	if flag & 1 == 1 {
+		nibbles.push(first & 0x0f);
+	}
+	for b in &path[1..] {
+		nibbles.push(b >> 4);
+		nibbles.push(b & 0x0f);
+	}
+	Ok((nibbles, flag & 2 == 2))
+}
+
+/// Payload of the rlp item starting `data`, whether it is a list, and the bytes after it.
+fn rlp_item(data: &[u8]) -> Result<(&[u8], bool, &[u8]), &'static str> {
+	let first = *data.first().ok_or("error rlp item empty")?;
+	let (offset, len, is_list) = match first {
+		0x00..=0x7f => return Ok((&data[..1], false, &data[1..])),
+		0x80..=0xb7 => (1, (first - 0x80) as usize, false),
+		0xb8..=0xbf => {
+			let n = (first - 0xb7) as usize;
+			(1 + n, rlp_len(data.get(1..1 + n).ok_or("error rlp too short")?)?, false)
+		},
+		0xc0..=0xf7 => (1, (first - 0xc0) as usize, true),
+		_ => {
+			let n = (first - 0xf7) as usize;
+			(1 + n, rlp_len(data.get(1..1 + n).ok_or("error rlp too short")?)?, true)
+		},
+	};
+	let end = offset.checked_add(len).ok_or("error rlp too short")?;
+	let payload = data.get(offset..end).ok_or("error rlp too short")?;
+	Ok((payload, is_list, &data[end..]))
+}
+
+fn rlp_len(bytes: &[u8]) -> Result<usize, &'static str> {
+	if bytes.len() > 8 || bytes.first() == Some(&0) {
+		return Err("error rlp invalid length");
+	}
+	let mut len = 0u64;
+	for b in bytes {
+		len = (len << 8) | *b as u64;
+	}
+	len.try_into().map_err(|_| "error rlp invalid length")
+}
+
+/// Encoded items of the rlp list making up all of `data`.
+fn rlp_list(data: &[u8]) -> Result<Vec<&[u8]>, &'static str> {
+	let (mut payload, is_list, rest) = rlp_item(data)?;
+	if !is_list || !rest.is_empty() {
+		return Err("error rlp is not a list");
+	}
+	let mut items = Vec::new();
+	while !payload.is_empty() {
+		let (_, _, after) = rlp_item(payload)?;
+		items.push(&payload[..payload.len() - after.len()]);
+		payload = after;
+	}
+	Ok(items)
+}
+
+fn rlp_bytes(item: &[u8]) -> Result<&[u8], &'static str> {
+	match rlp_item(item)? {
+		(payload, false, rest) if rest.is_empty() => Ok(payload),
+		_ => Err("error rlp is not a string"),
+	}
+}
+
+fn rlp_word(item: &[u8]) -> Result<Word, &'static str> {
+	let bytes = rlp_bytes(item)?;
+	if bytes.len() != 32 {
+		return Err("error rlp word is not 32 bytes");
+	}
+	let mut word = [0u8; 32];
+	word.copy_from_slice(bytes);
+	Ok(word)
+}
+
+fn rlp_uint(item: &[u8]) -> Result<u128, &'static str> {
+	let bytes = rlp_bytes(item)?;
+	if bytes.len() > 16 {
+		return Err("error rlp uint overflow");
+	}
+	Ok(bytes.iter().fold(0u128, |n, b| (n << 8) | *b as u128))
+}
@@ -0,0 +1,1989 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod eth;
+mod tests;
+
+use codec::{Decode, Encode};
+use frame_support::{
+	debug::native,
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	traits::{
+		schedule::Named as ScheduleNamed, BalanceStatus, Currency, EnsureOrigin, ExistenceRequirement, Get,
+		ReservableCurrency,
+	},
+	weights::Weight,
+	StorageDoubleMap, StorageMap, StorageValue,
+};
+use frame_system::{
+	self as system, ensure_root, ensure_signed,
+	offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer},
+};
+use sp_runtime::app_crypto::{KeyTypeId, RuntimeAppPublic};
+use sp_runtime::{
+	offchain::storage::StorageValueRef,
+	traits::{Hash, IdentifyAccount, Saturating},
+	DispatchError, DispatchResult as dispatch_result,
+};
+use sp_std::{
+	convert::{Into, TryInto},
+	prelude::*,
+	result::Result,
+	vec::Vec,
+};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use utilities::{http, LinkedItem, LinkedList};
+
+extern crate num_bigint_dig as num_bigint;
+//extern crate num_traits;
+use num_bigint::{BigUint, ToBigUint};
+use num_traits::{One, Zero};
+
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orin");
+
+pub mod sr25519 {
+	use frame_system::offchain::AppCrypto;
+	use sp_runtime::{MultiSignature, MultiSigner};
+
+	mod app_sr25519 {
+		use sp_runtime::app_crypto::{app_crypto, sr25519};
+		app_crypto!(sr25519, super::super::KEY_TYPE);
+
+		impl From<Signature> for sp_runtime::AnySignature {
+			fn from(sig: Signature) -> Self {
+				sr25519::Signature::from(sig).into()
+			}
+		}
+	}
+
+	pub type AuthorityId = app_sr25519::Public;
+
+	/// Signs oracle submissions with the local `orin` keys.
+	pub struct AuthId;
+
+	impl AppCrypto<MultiSigner, MultiSignature> for AuthId {
+		type RuntimeAppPublic = AuthorityId;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct EventLogSource {
+	event_type: Vec<u8>,
+	event_url: Vec<u8>,
+}
+
+/// Ethereum logs scanned by the offchain worker.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ScanConfig {
+	contract_addr: Vec<u8>, //hex with 0x prefix
+	topics: Vec<Vec<u8>>, //event signatures matched as topic0
+	start_block: u64, //first ethereum block to scan
+	confirmations: u64, //blocks behind the ethereum head considered final
+	max_step: u64, //most blocks scanned per run
+}
+
+/// Requirements for events proven against tracked ethereum headers.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct ProofConfig {
+	min_difficulty: u128, //lowest difficulty accepted for a relayed header
+	confirmations: u64, //headers on top of the one holding the event
+}
+
+/// Receipt trie proof of a swap contract log.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct EventProof {
+	header: Vec<u8>, //rlp encoded block header
+	tx_index: u64,
+	receipt_proof: Vec<Vec<u8>>, //trie nodes from the receipts root to the receipt
+	log_index: u32, //index of the log within the receipt
+}
+
+/// An ethereum block header tracked for verifying receipt proofs.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct EthHeader {
+	number: u64,
+	parent_hash: [u8; 32],
+	receipts_root: [u8; 32],
+	timestamp: u64,
+	difficulty: u128,
+	total_difficulty: u128,
+}
+
+// deadline in milliseconds and retries of event source requests
+const HTTP_TIMEOUT: u64 = 30_000;
+const HTTP_RETRIES: u32 = 2;
+
+/// Offchain local storage key of an `http::Endpoint` replacing the event url set by `kickoff`.
+pub const ENDPOINT_KEY: &[u8] = b"oracle::endpoint";
+
+// offchain local storage key of (contract_addr, last scanned ethereum block)
+const SCAN_CURSOR_KEY: &[u8] = b"oracle::scan_cursor";
+
+const STR_PREFIX: &'static str = "0x";
+
+//event source types
+const EVENT_SRC_ETHERSCAN: &'static [u8; 9] = b"etherscan";
+const EVENT_SRC_INFURA: &'static [u8; 6] = b"infura";
+
+// TODO: auto generate EventSignature by contract abi
+const EVENT_SIG_HTLC: &'static str =
+	"0x5a0cc384a12a55445d4625db5d24f6a72177fd330644e2d4b3ea0ebd6f78c54d";
+const EVENT_SIG_CLAIM: &'static str =
+	"0x07a9dd1ef03da239626dc5c5bac1995991043d2b6e0e23ca789bbc0a16eb911f";
+const EVENT_SIG_REFUND: &'static str =
+	"0x215e15eef6d0300f9e89d940198e4f7fc22e44b7c80118c03571cd96da6c6c98";
+
+const B_ALPHA: &'static [u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct EventHTLC<BlockNumber, Balance, Hash> {
+	eth_contract_addr: Vec<u8>,
+	htlc_block_number: BlockNumber,
+	event_block_number: BlockNumber,
+	expire_height: u32,
+	random_number_hash: Vec<u8>, //When event_type is Claimed，value is random_number instead of hash
+	swap_id: Hash,
+	// event_timestamp: u64,
+	htlc_timestamp: u64, //Part of the random_number_hash preimage, 0 for Claimed and Refunded
+	sender_addr: Vec<u8>,
+	sender_chain_type: HTLCChain,
+	receiver_addr: Hash,
+	receiver_chain_type: HTLCChain,
+	recipient_addr: Vec<u8>,
+	out_amount: Balance,
+	event_type: HTLCType,
+}
+
+/// Native PRM locked on this chain for a swap to ethereum.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct NativeHTLC<AccountId, Balance, BlockNumber> {
+	sender: AccountId,
+	recipient_addr: Vec<u8>, //Ethereum address receiving the ERC20 tokens
+	random_number_hash: [u8; 32], //sha256(random_number ‖ timestamp)
+	timestamp: u64,
+	expire_at: BlockNumber,
+	amount: Balance,
+}
+
+/// Bounds on ethereum swaps paid out from the lock account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct SwapLimit<Balance, BlockNumber> {
+	max_amount: Balance, //larger swaps are rejected
+	daily_cap: Balance, //most released within the volume window
+	delay_threshold: Balance, //larger claims are released after release_delay
+	release_delay: BlockNumber, //blocks the council has to cancel a large release
+}
+
+/// Amounts of swaps opened and settled in one direction.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, Debug)]
+pub struct SwapTotals<Balance> {
+	pub opened: Balance,
+	pub claimed: Balance,
+	pub refunded: Balance,
+}
+
+/// Reconciliation report returned by the runtime API.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, Debug)]
+pub struct Reconciliation<Balance> {
+	/// Ethereum swaps paid out from the lock account.
+	pub eth: SwapTotals<Balance>,
+	/// Native swaps locked on this chain.
+	pub native: SwapTotals<Balance>,
+	/// Amount of ethereum swaps neither claimed nor refunded.
+	pub outstanding: Balance,
+	/// Free balance of the lock account.
+	pub available: Balance,
+	/// Whether new ethereum swaps are rejected by the circuit breaker.
+	pub halted: bool,
+}
+
+/// A swap as returned by the runtime API.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct SwapInfo<AccountId, Hash, Balance, BlockNumber> {
+	pub swap_id: Hash,
+	pub state: HTLCStates,
+	/// Ethereum swap data, removed once claimed.
+	pub eth: Option<EventHTLC<BlockNumber, Balance, Hash>>,
+	pub native: Option<NativeHTLC<AccountId, Balance, BlockNumber>>,
+	pub frozen: bool,
+	/// A verified claim waits for release.
+	pub held: bool,
+	/// Block a queued large claim is released at.
+	pub release_at: Option<BlockNumber>,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Debug)]
+pub enum HTLCStates {
+	INVALID,
+	OPEN,
+	COMPLETED,
+	EXPIRED,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Debug)]
+pub enum HTLCChain {
+	/// Ethereum Mainnet
+	ETHMain,
+	/// Prochain
+	PRM,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone, Debug)]
+pub enum HTLCType {
+	HTLC,
+	Claimed,
+	Refunded,
+}
+
+/// Hook for authorities whose attestation conflicts with the finalized event.
+pub trait ReportConflict<AccountId, Hash> {
+	/// `who` attested `reported` for `swap_id` while `finalized` reached the threshold.
+	fn report_conflict(who: &AccountId, swap_id: &Hash, reported: &Hash, finalized: &Hash);
+}
+
+impl<AccountId, Hash> ReportConflict<AccountId, Hash> for () {
+	fn report_conflict(_: &AccountId, _: &Hash, _: &Hash, _: &Hash) {}
+}
+
+//  automates offchain fetching every certain blocks
+pub const BLOCK_DURATION: u64 = 5;
+
+// ethereum heights checked for expired swaps per block
+pub const MAX_EXPIRY_SWEEP: u32 = 100;
+
+// swaps expired per block, the rest are left for the following blocks
+pub const MAX_EXPIRIES_PER_BLOCK: u32 = 50;
+
+// execution weight of locking, paying out or expiring one swap, besides its storage access
+const SWAP_WEIGHT: Weight = 50_000_000;
+
+// receipt proofs accepted per call
+pub const MAX_PROOFS_PER_CALL: u32 = 16;
+
+// attested events accepted per call
+pub const MAX_EVENTS_PER_CALL: u32 = 32;
+
+// execution weight per byte of the header and trie nodes hashed to verify a proof
+const PROOF_BYTE_WEIGHT: Weight = 5_000;
+
+// ethereum headers relayed per call
+pub const MAX_HEADERS_PER_CALL: u32 = 32;
+
+// volume window is tracked in this many buckets
+pub const VOLUME_BUCKETS: u32 = 24;
+
+// scheduler id prefix of delayed swap releases
+const SWAP_RELEASE_ID: [u8; 8] = *b"swaprels";
+
+// ethereum headers kept behind the latest imported one, bounds the age of provable events
+pub const ETH_HEADERS_KEPT: u64 = 256;
+
+// max swaps returned by a single query
+pub const MAX_QUERY_LIMIT: u32 = 100;
+
+type OpenSwapList<T> = LinkedList<OpenSwaps<T>, <T as system::Trait>::Hash, OpenSwapsCount>;
+
+pub trait Trait: pallet_balances::Trait + pallet_timestamp::Trait + did::Trait + CreateSignedTransaction<Call<Self>> {
+	/// Crypto of the `orin` keys signing oracle submissions.
+	type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+	/// A dispatchable call type.
+	type Call: From<Call<Self>>;
+	/// Slashing hook for authorities attesting a conflicting event.
+	type OnConflict: ReportConflict<Self::AccountId, Self::Hash>;
+	/// Origin allowed to pause swaps, freeze single swaps and rotate authorities.
+	type CouncilOrigin: EnsureOrigin<Self::Origin>;
+	/// Scheduler releasing large swaps after a delay.
+	type Scheduler: ScheduleNamed<Self::BlockNumber, <Self as Trait>::Call>;
+	/// Blocks over which released volume counts against the daily cap.
+	type VolumeWindow: Get<Self::BlockNumber>;
+	/// Smallest amount a native swap may lock.
+	type MinSwapAmount: Get<Self::Balance>;
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// invlid event type
+		InvalidEventType,
+
+		/// invlid did
+		InvalidDidType,
+
+		/// invlid event source type
+		InvalidEventSrcType,
+
+		/// scan needs a contract, topics and a positive step
+		InvalidScanConfig,
+
+		/// event urls set on chain must not carry API keys or other credentials
+		ApiKeyInUrl,
+
+		/// sender is not an oracle authority
+		NotAuthority,
+
+		/// threshold must be between 1 and the number of authorities
+		InvalidThreshold,
+
+		/// revealed random number does not match the swap's random_number_hash
+		InvalidRandomNumber,
+
+		/// swap_id already exists
+		SwapExists,
+
+		/// swap_id not exists or not open
+		SwapNotOpen,
+
+		/// swap expired and can only be refunded
+		SwapExpired,
+
+		/// swap not expired yet
+		SwapNotExpired,
+
+		/// amount and expire height must be positive
+		InvalidSwapParams,
+
+		/// amount is below the minimum swap amount
+		SwapAmountTooLow,
+
+		/// recipient is not a valid ethereum address
+		InvalidRecipientAddr,
+
+		/// events must be submitted with a receipt proof
+		ProofRequired,
+
+		/// receipt proofs are not enabled
+		ProofNotEnabled,
+
+		/// ethereum header is not tracked or its parent is unknown
+		UnknownEthHeader,
+
+		/// ethereum header does not extend its parent or its difficulty is too low
+		InvalidEthHeader,
+
+		/// ethereum header is not on the best chain with enough confirmations
+		EthHeaderNotConfirmed,
+
+		/// receipt proof does not match the header or holds no such log
+		InvalidReceiptProof,
+
+		/// more receipt proofs than accepted in one call
+		TooManyProofs,
+
+		/// more ethereum headers than accepted in one call
+		TooManyHeaders,
+
+		/// more events than accepted in one call
+		TooManyEvents,
+
+		/// swap of a claim or refund is not known yet
+		UnknownSwap,
+
+		/// log was not emitted by the scanned swap contract
+		UnknownContract,
+
+		/// lock account balance does not cover the open swaps
+		Insolvent,
+
+		/// swap payouts are paused
+		Paused,
+
+		/// swap is frozen
+		SwapFrozen,
+
+		/// swap has no claim waiting for release
+		NoHeldClaim,
+
+		/// swap has no release queued
+		NoQueuedRelease,
+
+		/// release would exceed the daily cap
+		DailyCapExceeded,
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as OracleSwap {
+		/// Stores the locked prm tokens
+		pub PraTokenAddr get(fn pra_token_addr): Option<T::AccountId>;
+
+		/// The current set of keys that may attest events
+		pub Authorities get(fn authorities) config(): Vec<T::AccountId>;
+
+		/// Matching attestations needed before an event takes effect
+		pub Threshold get(fn threshold) config(): u32;
+
+		/// Key is (swap_id, event_type), value is the authorities and the event hash each attested
+		pub Reports get(fn reports): map hasher(twox_64_concat) (T::Hash, HTLCType) => Vec<(T::AccountId, T::Hash)>;
+
+		/// Key is event hash, value is the attested event waiting for the threshold
+		pub PendingEvents get(fn pending_events): map hasher(twox_64_concat) T::Hash => Option<EventHTLC<T::BlockNumber, T::Balance, T::Hash>>;
+
+		/// Key is (swap_id, event_type), value is the event hash that reached the threshold
+		pub Finalized get(fn finalized): map hasher(twox_64_concat) (T::Hash, HTLCType) => Option<T::Hash>;
+
+		/// Event source the offchain worker scans
+		pub OcRequests get(fn oc_requests): Option<EventLogSource>;
+
+		/// Contract, topics and pacing of the ethereum log scan
+		pub ScanConfigs get(fn scan_config): Option<ScanConfig>;
+
+		/// Key is swap_id, value is EventHTLC, should be removed after completed
+		pub SwapData get(fn swap_data): map hasher(twox_64_concat) T::Hash => Option<EventHTLC<T::BlockNumber, T::Balance, T::Hash>>;
+
+		/// Key is swap_id, Value is HTLCStates, Note: should never be removed
+		pub SwapStates get(fn swap_states): map hasher(twox_64_concat) T::Hash => Option<HTLCStates>;
+
+		/// Total count in SwapStates, Note: should always be larger
+		pub SwapStatesCount get(fn swap_states_count): u64;
+
+		/// Swaps in OPEN state, linked in the order they were opened
+		pub OpenSwaps get(fn open_swap_link): map hasher(twox_64_concat) Option<T::Hash> => Option<LinkedItem<T::Hash>>;
+		pub OpenSwapsCount get(fn open_swaps_count): u64;
+
+		/// Highest ethereum block number of an event that reached the threshold
+		pub EthBlockNumber get(fn eth_block_number): T::BlockNumber;
+
+		/// Ethereum block number up to which expired swaps have been swept
+		pub EthExpirySwept get(fn eth_expiry_swept): T::BlockNumber;
+
+		/// Key is the ethereum block number an ethereum swap expires at, value is swap_ids
+		pub EthExpiries get(fn eth_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<T::Hash>;
+
+		/// Key is the block number a native swap expires at, value is swap_ids
+		pub NativeExpiries get(fn native_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<T::Hash>;
+
+		/// Key is swap_id, value is the native HTLC locked on this chain
+		pub NativeSwaps get(fn native_swaps): map hasher(twox_64_concat) T::Hash => Option<NativeHTLC<T::AccountId, T::Balance, T::BlockNumber>>;
+
+		/// Key is swap_id, value is (random_number_hash, random_number) revealed by the claim,
+		/// hex encoded for ethereum swaps and raw bytes for native swaps
+		pub SwapSecrets get(fn swap_secrets): map hasher(twox_64_concat) T::Hash => Option<(Vec<u8>, Vec<u8>)>;
+
+		/// Requirements for proven events, when set events are only accepted with receipt proofs
+		pub ProofConfigs get(fn proof_config): Option<ProofConfig>;
+
+		/// Key is the ethereum block hash, value is the tracked header
+		pub EthHeaders get(fn eth_header): map hasher(twox_64_concat) [u8; 32] => Option<EthHeader>;
+
+		/// Key is the ethereum block number, value is the hashes of tracked headers at that number
+		pub EthHeaderHashes get(fn eth_header_hashes): map hasher(twox_64_concat) u64 => Vec<[u8; 32]>;
+
+		/// Hash of the tracked header with the most total difficulty
+		pub EthBestHeader get(fn eth_best_header): Option<[u8; 32]>;
+
+		/// Keys are the ethereum block number and hash of a header not tracked yet, value is the
+		/// authorities that relayed it
+		pub EthHeaderVotes get(fn eth_header_votes):
+			double_map hasher(twox_64_concat) u64, hasher(twox_64_concat) [u8; 32] => Vec<T::AccountId>;
+
+		/// Amounts of ethereum swaps paid out from PraTokenAddr
+		pub EthSwapTotals get(fn eth_swap_totals): SwapTotals<T::Balance>;
+
+		/// Amounts of native swaps locked on this chain
+		pub NativeSwapTotals get(fn native_swap_totals): SwapTotals<T::Balance>;
+
+		/// Set when PraTokenAddr could not cover the open swaps, new ethereum swaps are rejected until resumed
+		pub SwapsHalted get(fn swaps_halted): bool;
+
+		/// Emergency pause, no swap pays out while set
+		pub Paused get(fn paused): bool;
+
+		/// Key is swap_id, frozen swaps do not pay out until unfrozen
+		pub FrozenSwaps get(fn frozen_swaps): map hasher(twox_64_concat) T::Hash => bool;
+
+		/// Key is swap_id, value is the verified random number of a claim held by a pause, freeze or the daily cap
+		pub HeldClaims get(fn held_claims): map hasher(twox_64_concat) T::Hash => Option<Vec<u8>>;
+
+		/// Per swap, daily and delayed release limits, unlimited when unset
+		pub SwapLimits get(fn swap_limits): Option<SwapLimit<T::Balance, T::BlockNumber>>;
+
+		/// Released amounts by volume bucket within the volume window
+		pub ReleasedVolume get(fn released_volume): Vec<(T::BlockNumber, T::Balance)>;
+
+		/// Key is swap_id, value is the release block and verified random number of a large claim
+		pub QueuedReleases get(fn queued_releases): map hasher(twox_64_concat) T::Hash => Option<(T::BlockNumber, Vec<u8>)>;
+
+		/// Keys are the receiver did hash and the position, value is the swap_id of an accepted
+		/// ethereum swap paying it
+		pub ReceiverSwaps get(fn receiver_swap):
+			double_map hasher(twox_64_concat) T::Hash, hasher(twox_64_concat) u32 => Option<T::Hash>;
+
+		/// Key is the receiver did hash, value is the number of swaps in `ReceiverSwaps`
+		pub ReceiverSwapsCount get(fn receiver_swaps_count): map hasher(twox_64_concat) T::Hash => u32;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as system::Trait>::BlockNumber,
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as pallet_balances::Trait>::Balance,
+	{
+		///Setup and kickoff event_type, event_url, contract_addr
+		Kickoff(Vec<u8>, Vec<u8>, Vec<u8>),
+
+		///kill scanned event_name and event_url, make sure run only once
+		//Kill(Vec<u8>, Vec<u8>),
+
+		///receiver_addr, eth_contract_addr, htlc_block_number, expire_height, random_number_hash, swap_id, sender_addr, out_amount
+		HTLC(Hash, Vec<u8>, BlockNumber, u32, Vec<u8>, Hash, Vec<u8>, Balance),
+
+		///receiver_addr, eth_contract_addr, swap_id, sender_addr, random_number
+		Claim(Hash, Vec<u8>, Hash, Vec<u8>,Vec<u8>),
+
+		///receiver_addr, eth_contract_addr, sender_addr, random_number_hash
+		Refund(Hash, Vec<u8>, Hash, Vec<u8>, Vec<u8>),
+
+		///sender_account, receiver_account, receiver_did, out_amount
+		TransferToDid(AccountId, AccountId, Hash, Balance),
+
+		//did hex str, did account
+		TryParseDid(Vec<u8>, AccountId),
+
+		///authorities, threshold
+		AuthoritiesSet(Vec<AccountId>, u32),
+
+		///authority, swap_id, event_hash, matching attestations
+		Attested(AccountId, Hash, Hash, u32),
+
+		///authority, swap_id, reported event_hash, finalized event_hash
+		ConflictingReport(AccountId, Hash, Hash, Hash),
+
+		///swap_id, sender, recipient_addr, random_number_hash, timestamp, expire_at, amount
+		NativeHTLC(Hash, AccountId, Vec<u8>, [u8; 32], u64, BlockNumber, Balance),
+
+		///swap_id, random_number
+		NativeClaim(Hash, [u8; 32]),
+
+		///swap_id, sender, amount
+		NativeRefund(Hash, AccountId, Balance),
+
+		///swap_id of an ethereum swap passing its expire height
+		Expired(Hash),
+
+		///ethereum block hash, block number
+		EthHeaderImported([u8; 32], u64),
+
+		///swap_id of an ethereum swap rejected while halted or insolvent, over the limit or paying an unknown did
+		SwapRejected(Hash),
+
+		///required, available balance of PraTokenAddr when the circuit breaker tripped
+		SwapsHalted(Balance, Balance),
+
+		///ethereum swaps accepted again
+		SwapsResumed,
+
+		///swap payouts paused by the council
+		Paused,
+
+		///swap payouts resumed by the council
+		Resumed,
+
+		///swap_id frozen by the council
+		SwapFrozen(Hash),
+
+		///swap_id unfrozen by the council
+		SwapUnfrozen(Hash),
+
+		///swap_id of a verified claim held until released
+		ClaimHeld(Hash),
+
+		///swap_id, block number the claim is released at
+		ReleaseQueued(Hash, BlockNumber),
+
+		///swap_id of a queued claim paid out
+		SwapReleased(Hash),
+
+		///swap_id of a queued claim cancelled by the council, the swap is frozen
+		ReleaseCancelled(Hash),
+
+		///swap_id, error of an event left out of its batch, it can be submitted again
+		EventFailed(Hash, DispatchError),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		const MinSwapAmount: T::Balance = T::MinSwapAmount::get();
+
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let native = Self::expire_native_swaps(now, MAX_EXPIRIES_PER_BLOCK);
+			let (heights, eth) = Self::expire_eth_swaps(MAX_EXPIRIES_PER_BLOCK.saturating_sub(native));
+			Self::expiry_weight(heights, native + eth)
+		}
+
+		// Start scanning ethereum logs from the given source. API keys are set in offchain local
+		// storage under ENDPOINT_KEY, event_url may be left empty.
+		#[weight = 0]
+		fn kickoff(origin, event_src_type: Vec<u8>, event_url: Vec<u8>, config: ScanConfig) -> dispatch_result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_authority(&sender), Error::<T>::NotAuthority);
+			ensure!(!Self::has_credentials(&event_url), Error::<T>::ApiKeyInUrl);
+
+			if event_src_type != EVENT_SRC_ETHERSCAN && event_src_type != EVENT_SRC_INFURA {
+				return Err(Error::<T>::InvalidEventSrcType)?;
+			}
+			ensure!(
+				!config.contract_addr.is_empty() && !config.topics.is_empty() && config.max_step > 0,
+				Error::<T>::InvalidScanConfig
+			);
+
+			native::info!(target: "swap", "kickoff event scan");
+
+			let event_src = EventLogSource {
+				event_type: event_src_type.clone(),
+				event_url: event_url.clone(),
+			};
+			let contract_addr = config.contract_addr.clone();
+			<Self as Store>::OcRequests::put(event_src);
+			<Self as Store>::ScanConfigs::put(config);
+			Self::deposit_event(RawEvent::Kickoff(event_src_type, event_url, contract_addr));
+			Ok(())
+		}
+
+		// Stop scanning ethereum logs
+		#[weight = 0]
+		fn killall(origin) -> dispatch_result {
+			let sender = ensure_signed(origin)?;
+
+			if Self::is_authority(&sender) {
+				<Self as Store>::OcRequests::kill();
+				<Self as Store>::ScanConfigs::kill();
+			}
+
+			Ok(())
+		}
+
+		// Try parse did in hex str format
+		#[weight = 0]
+		fn try_parse_did(origin, did_hex: Vec<u8>) -> dispatch_result {
+			let _ = ensure_signed(origin)?;
+
+			if let Ok(account) = Self::parse_did(&did_hex) {
+				Self::deposit_event(RawEvent::TryParseDid(did_hex, account));
+				return Ok(());
+			}
+			Err(Error::<T>::InvalidDidType)?
+		}
+
+		// Set the authorities allowed to attest events and how many must agree.
+		#[weight = 0]
+		fn init(origin, auths: Vec<T::AccountId>, threshold: u32, pra_token_addr: T::AccountId) -> dispatch_result {
+			ensure_root(origin)?;
+
+			Self::put_authorities(auths, threshold)?;
+			<PraTokenAddr<T>>::put(pra_token_addr.clone());
+			<SwapStatesCount>::put(0);
+			Ok(())
+		}
+
+		// Rotate the authorities, reports of removed authorities no longer count.
+		#[weight = 0]
+		fn set_authorities(origin, auths: Vec<T::AccountId>, threshold: u32) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+			Self::put_authorities(auths, threshold)
+		}
+
+		// Stop all swap payouts, verified claims are held until resumed.
+		#[weight = 0]
+		fn pause(origin) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+			<Paused>::put(true);
+			Self::deposit_event(RawEvent::Paused);
+			Ok(())
+		}
+
+		#[weight = 0]
+		fn resume(origin) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+			<Paused>::kill();
+			Self::deposit_event(RawEvent::Resumed);
+			Ok(())
+		}
+
+		// Stop payouts of a single swap.
+		#[weight = 0]
+		fn freeze_swap(origin, swap_id: T::Hash) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+			ensure!(<SwapStates<T>>::contains_key(swap_id), Error::<T>::SwapNotOpen);
+			<FrozenSwaps<T>>::insert(swap_id, true);
+			Self::deposit_event(RawEvent::SwapFrozen(swap_id));
+			Ok(())
+		}
+
+		#[weight = 0]
+		fn unfreeze_swap(origin, swap_id: T::Hash) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+			<FrozenSwaps<T>>::remove(swap_id);
+			Self::deposit_event(RawEvent::SwapUnfrozen(swap_id));
+			Ok(())
+		}
+
+		// Pay out a claim held while paused or frozen.
+		#[weight = SWAP_WEIGHT + T::DbWeight::get().reads_writes(14, 12)]
+		fn release_claim(origin, swap_id: T::Hash) -> dispatch_result {
+			let _ = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::<T>::Paused);
+			ensure!(!Self::frozen_swaps(swap_id), Error::<T>::SwapFrozen);
+			let random_number = Self::held_claims(swap_id).ok_or(Error::<T>::NoHeldClaim)?;
+			let htlc = Self::swap_data(swap_id).ok_or(Error::<T>::SwapNotOpen)?;
+			ensure!(Self::within_daily_cap(htlc.out_amount), Error::<T>::DailyCapExceeded);
+
+			Self::settle_claim(swap_id, htlc, random_number, false)?;
+			<HeldClaims<T>>::remove(swap_id);
+			Ok(())
+		}
+
+		// Set the per swap, daily and delayed release limits, or lift them with `None`.
+		#[weight = 0]
+		fn set_swap_limits(origin, limits: Option<SwapLimit<T::Balance, T::BlockNumber>>) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+
+			match limits {
+				Some(limits) => <SwapLimits<T>>::put(limits),
+				None => <SwapLimits<T>>::kill(),
+			}
+			Ok(())
+		}
+
+		// Pay out a queued claim once its delay passed, dispatched by the scheduler.
+		#[weight = 0]
+		fn release_swap(origin, swap_id: T::Hash) -> dispatch_result {
+			ensure_root(origin)?;
+			let (_, random_number) = <QueuedReleases<T>>::take(swap_id).ok_or(Error::<T>::NoQueuedRelease)?;
+			let htlc = Self::swap_data(swap_id).ok_or(Error::<T>::SwapNotOpen)?;
+
+			// keep the claim if it cannot be paid out now
+			if Self::settle_claim(swap_id, htlc, random_number.clone(), true).is_err() {
+				<HeldClaims<T>>::insert(swap_id, random_number);
+				Self::deposit_event(RawEvent::ClaimHeld(swap_id));
+			} else if Self::swap_states(swap_id) == Some(HTLCStates::COMPLETED) {
+				Self::deposit_event(RawEvent::SwapReleased(swap_id));
+			}
+			Ok(())
+		}
+
+		// Stop a queued release, the claim is held and the swap frozen for review.
+		#[weight = 0]
+		fn cancel_release(origin, swap_id: T::Hash) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+			let (_, random_number) = Self::queued_releases(swap_id).ok_or(Error::<T>::NoQueuedRelease)?;
+			T::Scheduler::cancel_named(Self::release_id(&swap_id)).map_err(|_| "error cancelling swap release")?;
+
+			<QueuedReleases<T>>::remove(swap_id);
+			<HeldClaims<T>>::insert(swap_id, random_number);
+			<FrozenSwaps<T>>::insert(swap_id, true);
+			Self::deposit_event(RawEvent::ReleaseCancelled(swap_id));
+			Ok(())
+		}
+
+		// Runs after every block.
+		fn offchain_worker(now: T::BlockNumber) {
+			frame_support::debug::RuntimeLogger::init();
+			//if BLOCK_DURATION > 0 && (TryInto::<u64>::try_into(now).ok().unwrap()) % BLOCK_DURATION == 0 {
+			Self::offchain_events(now);
+			//}
+		}
+
+		// Stores valid swap data and states
+		#[weight = <Module<T>>::event_htlcs_weight(htlcs.len())]
+		fn update_enevt_htlc(origin, htlcs: Vec<EventHTLC<T::BlockNumber, T::Balance, T::Hash>>) -> dispatch_result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_authority(&sender), Error::<T>::NotAuthority);
+			ensure!(Self::proof_config().is_none(), Error::<T>::ProofRequired);
+			ensure!(htlcs.len() <= MAX_EVENTS_PER_CALL as usize, Error::<T>::TooManyEvents);
+
+			ensure!(Self::pra_token_addr().is_some(), "error not valid pra_token_addr");
+
+			// dispatch is not transactional, a failed event must not undo or stop the others
+			for htlc in htlcs {
+				let swap_id = htlc.swap_id;
+				if let Err(e) = Self::attest(sender.clone(), htlc) {
+					native::warn!(target: "swap", "event of swap {:?} failed: {:?}", swap_id, e);
+					Self::deposit_event(RawEvent::EventFailed(swap_id, e));
+				}
+			}
+			Ok(())
+		}
+
+		// Accept new ethereum swaps again once PraTokenAddr covers the open ones.
+		#[weight = 0]
+		fn resume_swaps(origin) -> dispatch_result {
+			T::CouncilOrigin::ensure_origin(origin)?;
+			let pra_token_addr = Self::pra_token_addr().ok_or("error not valid pra_token_addr")?;
+			ensure!(Self::free_balance(&pra_token_addr) >= Self::outstanding(), Error::<T>::Insolvent);
+
+			<SwapsHalted>::kill();
+			Self::deposit_event(RawEvent::SwapsResumed);
+			Ok(())
+		}
+
+		// Require receipt proofs for events, or go back to attestations with `None`.
+		#[weight = 0]
+		fn set_proof_config(origin, config: Option<ProofConfig>) -> dispatch_result {
+			ensure_root(origin)?;
+
+			match config {
+				Some(config) => <ProofConfigs>::put(config),
+				None => <ProofConfigs>::kill(),
+			}
+			Ok(())
+		}
+
+		// Track a trusted ethereum header with its total difficulty as the best header.
+		#[weight = 0]
+		fn set_eth_checkpoint(origin, header: Vec<u8>, total_difficulty: u128) -> dispatch_result {
+			ensure_root(origin)?;
+
+			let header = eth::decode_header(&header)?;
+			let hash = header.hash;
+			let number = header.number;
+			<EthHeaders>::insert(hash, EthHeader {
+				number,
+				parent_hash: header.parent_hash,
+				receipts_root: header.receipts_root,
+				timestamp: header.timestamp,
+				difficulty: header.difficulty,
+				total_difficulty,
+			});
+			<EthHeaderHashes>::mutate(number, |hashes| hashes.push(hash));
+			<EthBestHeader>::put(hash);
+			Self::deposit_event(RawEvent::EthHeaderImported(hash, number));
+			Ok(())
+		}
+
+		// Extend the tracked ethereum headers, each header's parent must be tracked.
+		// The ethash seal is not verified, so a header is only tracked once `Threshold`
+		// authorities relayed it.
+		#[weight = <Module<T>>::eth_headers_weight(headers)]
+		fn submit_eth_headers(origin, headers: Vec<Vec<u8>>) -> dispatch_result {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_authority(&sender), Error::<T>::NotAuthority);
+			let config = Self::proof_config().ok_or(Error::<T>::ProofNotEnabled)?;
+			ensure!(headers.len() <= MAX_HEADERS_PER_CALL as usize, Error::<T>::TooManyHeaders);
+
+			for header in headers {
+				Self::relay_eth_header(&sender, &header, &config)?;
+			}
+			Ok(())
+		}
+
+		// Apply events whose logs are proven against confirmed ethereum headers.
+		#[weight = <Module<T>>::event_proofs_weight(proofs)]
+		fn update_event_proofs(origin, proofs: Vec<EventProof>) -> dispatch_result {
+			let _ = ensure_signed(origin)?;
+			ensure!(proofs.len() <= MAX_PROOFS_PER_CALL as usize, Error::<T>::TooManyProofs);
+			let config = Self::proof_config().ok_or(Error::<T>::ProofNotEnabled)?;
+			ensure!(Self::pra_token_addr().is_some(), "error not valid pra_token_addr");
+
+			for proof in proofs {
+				let htlc = Self::verify_event_proof(&proof, &config)?;
+				Self::apply_proven(htlc)?;
+			}
+			Ok(())
+		}
+
+		// Lock native tokens for a swap to `recipient_addr` on ethereum, expiring after `expire_height` blocks.
+		#[weight = SWAP_WEIGHT + T::DbWeight::get().reads_writes(6, 9)]
+		fn htlc(origin, random_number_hash: [u8; 32], timestamp: u64, expire_height: T::BlockNumber, recipient_addr: Vec<u8>, amount: T::Balance) -> dispatch_result {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::<T>::Paused);
+			ensure!(!amount.is_zero() && !expire_height.is_zero(), Error::<T>::InvalidSwapParams);
+			ensure!(amount >= T::MinSwapAmount::get(), Error::<T>::SwapAmountTooLow);
+			ensure!(recipient_addr.len() == 20, Error::<T>::InvalidRecipientAddr);
+
+			let swap_id = T::Hashing::hash_of(&(random_number_hash, &sender, &recipient_addr));
+			ensure!(!<SwapStates<T>>::contains_key(swap_id), Error::<T>::SwapExists);
+			let new_count = Self::swap_states_count().checked_add(1).ok_or("Overflow adding swap_states_count")?;
+
+			<pallet_balances::Module<T> as ReservableCurrency<_>>::reserve(&sender, amount)?;
+
+			let expire_at = <system::Module<T>>::block_number() + expire_height;
+			let swap = NativeHTLC {
+				sender: sender.clone(),
+				recipient_addr: recipient_addr.clone(),
+				random_number_hash,
+				timestamp,
+				expire_at,
+				amount,
+			};
+			<NativeSwaps<T>>::insert(swap_id, swap);
+			<NativeExpiries<T>>::mutate(expire_at, |ids| ids.push(swap_id));
+			Self::set_swap_state(swap_id, HTLCStates::OPEN);
+			<SwapStatesCount>::put(new_count);
+			<NativeSwapTotals<T>>::mutate(|totals| totals.opened = totals.opened.saturating_add(amount));
+
+			Self::deposit_event(RawEvent::NativeHTLC(swap_id, sender, recipient_addr, random_number_hash, timestamp, expire_at, amount));
+			Ok(())
+		}
+
+		// Reveal the random number of an open native swap, releasing the locked tokens to the swap pool.
+		#[weight = SWAP_WEIGHT + T::DbWeight::get().reads_writes(10, 8)]
+		fn claim(origin, swap_id: T::Hash, random_number: [u8; 32]) -> dispatch_result {
+			let _ = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::<T>::Paused);
+			ensure!(!Self::frozen_swaps(swap_id), Error::<T>::SwapFrozen);
+			let swap = Self::native_swaps(swap_id).ok_or(Error::<T>::SwapNotOpen)?;
+			ensure!(Self::swap_states(swap_id) == Some(HTLCStates::OPEN), Error::<T>::SwapNotOpen);
+			ensure!(<system::Module<T>>::block_number() < swap.expire_at, Error::<T>::SwapExpired);
+			ensure!(
+				Self::verify_preimage(&random_number, &swap.random_number_hash, swap.timestamp),
+				Error::<T>::InvalidRandomNumber
+			);
+			let pra_token_addr = Self::pra_token_addr().ok_or("error not valid pra_token_addr")?;
+
+			<pallet_balances::Module<T> as ReservableCurrency<_>>::repatriate_reserved(
+				&swap.sender,
+				&pra_token_addr,
+				swap.amount,
+				BalanceStatus::Free,
+			)?;
+
+			Self::set_swap_state(swap_id, HTLCStates::COMPLETED);
+			<SwapSecrets<T>>::insert(swap_id, (swap.random_number_hash.to_vec(), random_number.to_vec()));
+			<NativeSwapTotals<T>>::mutate(|totals| totals.claimed = totals.claimed.saturating_add(swap.amount));
+			Self::deposit_event(RawEvent::NativeClaim(swap_id, random_number));
+			Ok(())
+		}
+
+		// Return the locked tokens of an expired native swap to its sender.
+		#[weight = SWAP_WEIGHT + T::DbWeight::get().reads_writes(7, 6)]
+		fn refund(origin, swap_id: T::Hash) -> dispatch_result {
+			let _ = ensure_signed(origin)?;
+			ensure!(!Self::frozen_swaps(swap_id), Error::<T>::SwapFrozen);
+			let swap = Self::native_swaps(swap_id).ok_or(Error::<T>::SwapNotOpen)?;
+			ensure!(Self::swap_states(swap_id) == Some(HTLCStates::OPEN), Error::<T>::SwapNotOpen);
+			ensure!(<system::Module<T>>::block_number() >= swap.expire_at, Error::<T>::SwapNotExpired);
+
+			Self::refund_native(swap_id, swap);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn offchain_events(_now: T::BlockNumber) {
+		let (source, config) = match (Self::oc_requests(), Self::scan_config()) {
+			(Some(source), Some(config)) => (source, config),
+			_ => return,
+		};
+		if Self::pra_token_addr().is_none() || Self::authority_keys().is_empty() {
+			return;
+		}
+		if Self::proof_config().is_some() {
+			native::info!(target: "swap", "events need receipt proofs, leaving them to relayers");
+			return;
+		}
+
+		if let Err(e) = Self::scan_events(&source, &config) {
+			native::error!(target: "swap", "scan events failed {:?}", e);
+		}
+	}
+
+	/// Hash identifying an event independent of the block an oracle observed it in.
+	fn event_hash(htlc: &EventHTLC<T::BlockNumber, T::Balance, T::Hash>) -> T::Hash {
+		let mut event = htlc.clone();
+		event.htlc_block_number = Zero::zero();
+		T::Hashing::hash_of(&event)
+	}
+
+	/// Record an authority's attestation, applying the event once enough authorities agree.
+	fn attest(who: T::AccountId, htlc: EventHTLC<T::BlockNumber, T::Balance, T::Hash>) -> dispatch_result {
+		let swap_id = htlc.swap_id;
+		let event_hash = Self::event_hash(&htlc);
+		let key = (swap_id, htlc.event_type.clone());
+
+		if let Some(finalized) = Self::finalized(&key) {
+			if finalized != event_hash {
+				Self::note_conflict(&who, &swap_id, &event_hash, &finalized);
+			}
+			return Ok(());
+		}
+
+		let mut reports = Self::reports(&key);
+		if reports.iter().any(|(reporter, _)| *reporter == who) {
+			return Ok(());
+		}
+		reports.push((who.clone(), event_hash));
+		let count = Self::attestations(&reports, &event_hash);
+
+		// claims and refunds attested before their swap stay pending until it is known
+		let known = htlc.event_type == HTLCType::HTLC || <SwapStates<T>>::contains_key(swap_id);
+		if count < Self::threshold() || !known {
+			if !<PendingEvents<T>>::contains_key(event_hash) {
+				<PendingEvents<T>>::insert(event_hash, htlc);
+			}
+			<Reports<T>>::insert(&key, reports);
+			Self::deposit_event(RawEvent::Attested(who, swap_id, event_hash, count));
+			return Ok(());
+		}
+
+		Self::finalize(key, event_hash, htlc, reports)?;
+		Self::deposit_event(RawEvent::Attested(who, swap_id, event_hash, count));
+		Ok(())
+	}
+
+	fn attestations(reports: &[(T::AccountId, T::Hash)], event_hash: &T::Hash) -> u32 {
+		reports
+			.iter()
+			.filter(|(reporter, hash)| hash == event_hash && Self::is_authority(reporter))
+			.count() as u32
+	}
+
+	/// Apply an event enough authorities agree on, reporting the ones attesting another.
+	fn finalize(
+		key: (T::Hash, HTLCType),
+		event_hash: T::Hash,
+		htlc: EventHTLC<T::BlockNumber, T::Balance, T::Hash>,
+		reports: Vec<(T::AccountId, T::Hash)>,
+	) -> dispatch_result {
+		Self::apply_event(htlc)?;
+
+		for (reporter, reported) in reports.iter().filter(|(_, hash)| *hash != event_hash) {
+			<PendingEvents<T>>::remove(reported);
+			Self::note_conflict(reporter, &key.0, reported, &event_hash);
+		}
+		<PendingEvents<T>>::remove(event_hash);
+		<Reports<T>>::remove(&key);
+		<Finalized<T>>::insert(&key, event_hash);
+		Ok(())
+	}
+
+	/// Apply the claim or refund of a new swap that reached the threshold before it.
+	fn apply_waiting(swap_id: T::Hash) {
+		for event_type in [HTLCType::Claimed, HTLCType::Refunded].iter() {
+			let key = (swap_id, event_type.clone());
+			let reports = Self::reports(&key);
+			let agreed = reports
+				.iter()
+				.map(|(_, hash)| *hash)
+				.find(|hash| Self::attestations(&reports, hash) >= Self::threshold());
+			if let Some((event_hash, htlc)) = agreed.and_then(|hash| Some((hash, Self::pending_events(hash)?))) {
+				if let Err(e) = Self::finalize(key, event_hash, htlc, reports) {
+					native::warn!(target: "swap", "waiting event of swap {:?} failed: {:?}", swap_id, e);
+				}
+			}
+		}
+	}
+
+	/// Weight of attesting `events` events, each may apply and pay out a swap.
+	fn event_htlcs_weight(events: usize) -> Weight {
+		let per_event = T::DbWeight::get().reads_writes(20, 16).saturating_add(SWAP_WEIGHT);
+		per_event.saturating_mul(events as Weight)
+	}
+
+	fn put_authorities(auths: Vec<T::AccountId>, threshold: u32) -> dispatch_result {
+		let mut auths = auths;
+		auths.sort();
+		auths.dedup();
+		ensure!(threshold > 0 && threshold as usize <= auths.len(), Error::<T>::InvalidThreshold);
+
+		<Authorities<T>>::put(&auths);
+		<Threshold>::put(threshold);
+		Self::deposit_event(RawEvent::AuthoritiesSet(auths, threshold));
+		Ok(())
+	}
+
+	fn note_conflict(who: &T::AccountId, swap_id: &T::Hash, reported: &T::Hash, finalized: &T::Hash) {
+		T::OnConflict::report_conflict(who, swap_id, reported, finalized);
+		Self::deposit_event(RawEvent::ConflictingReport(who.clone(), *swap_id, *reported, *finalized));
+	}
+
+	/// Apply an event proven by a receipt proof, no attestations needed.
+	fn apply_proven(htlc: EventHTLC<T::BlockNumber, T::Balance, T::Hash>) -> dispatch_result {
+		let key = (htlc.swap_id, htlc.event_type.clone());
+		if <Finalized<T>>::contains_key(&key) {
+			return Ok(());
+		}
+		let event_hash = Self::event_hash(&htlc);
+		Self::apply_event(htlc)?;
+
+		// attestations made before proofs were required
+		for (_, reported) in <Reports<T>>::take(&key) {
+			<PendingEvents<T>>::remove(reported);
+		}
+		<Finalized<T>>::insert(&key, event_hash);
+		Ok(())
+	}
+
+	/// Count an authority relaying a header, importing it once enough authorities relayed it.
+	fn relay_eth_header(who: &T::AccountId, rlp: &[u8], config: &ProofConfig) -> dispatch_result {
+		let header = eth::decode_header(rlp)?;
+		if <EthHeaders>::contains_key(header.hash) {
+			return Ok(());
+		}
+
+		let mut votes = Self::eth_header_votes(header.number, header.hash);
+		if !votes.contains(who) {
+			votes.push(who.clone());
+		}
+		let count = votes.iter().filter(|voter| Self::is_authority(voter)).count() as u32;
+		if count < Self::threshold() {
+			<EthHeaderVotes<T>>::insert(header.number, header.hash, votes);
+			return Ok(());
+		}
+
+		let (number, hash) = (header.number, header.hash);
+		Self::import_eth_header(header, config)?;
+		<EthHeaderVotes<T>>::remove(number, hash);
+		Ok(())
+	}
+
+	/// Import a header extending a tracked one, pruning headers older than `ETH_HEADERS_KEPT`.
+	fn import_eth_header(header: eth::Header, config: &ProofConfig) -> dispatch_result {
+		let parent = Self::eth_header(header.parent_hash).ok_or(Error::<T>::UnknownEthHeader)?;
+		ensure!(
+			header.number == parent.number + 1 && header.timestamp > parent.timestamp,
+			Error::<T>::InvalidEthHeader
+		);
+		// the difficulty adjustment lowers difficulty by at most 99/2048 of the parent's
+		let lowest = parent.difficulty - parent.difficulty / 2048 * 99;
+		ensure!(
+			header.difficulty >= lowest && header.difficulty >= config.min_difficulty,
+			Error::<T>::InvalidEthHeader
+		);
+		let total_difficulty = parent.total_difficulty.checked_add(header.difficulty).ok_or(Error::<T>::InvalidEthHeader)?;
+
+		<EthHeaders>::insert(header.hash, EthHeader {
+			number: header.number,
+			parent_hash: header.parent_hash,
+			receipts_root: header.receipts_root,
+			timestamp: header.timestamp,
+			difficulty: header.difficulty,
+			total_difficulty,
+		});
+		<EthHeaderHashes>::mutate(header.number, |hashes| hashes.push(header.hash));
+		let best_difficulty = Self::eth_best_header()
+			.and_then(Self::eth_header)
+			.map(|best| best.total_difficulty)
+			.unwrap_or(0);
+		if total_difficulty > best_difficulty {
+			<EthBestHeader>::put(header.hash);
+		}
+		if header.number > ETH_HEADERS_KEPT {
+			for hash in <EthHeaderHashes>::take(header.number - ETH_HEADERS_KEPT) {
+				<EthHeaders>::remove(hash);
+			}
+			<EthHeaderVotes<T>>::remove_prefix(header.number - ETH_HEADERS_KEPT);
+		}
+
+		Self::deposit_event(RawEvent::EthHeaderImported(header.hash, header.number));
+		Ok(())
+	}
+
+	/// Whether the tracked header is an ancestor of the best header by at least `confirmations`.
+	fn is_confirmed(hash: &[u8; 32], number: u64, confirmations: u64) -> bool {
+		let mut current = match Self::eth_best_header().and_then(|best| Self::eth_header(best).map(|header| (best, header))) {
+			Some(best) => best,
+			None => return false,
+		};
+		if current.1.number < number.saturating_add(confirmations) {
+			return false;
+		}
+		while current.1.number > number {
+			let parent_hash = current.1.parent_hash;
+			current = match Self::eth_header(parent_hash) {
+				Some(parent) => (parent_hash, parent),
+				None => return false,
+			};
+		}
+		current.0 == *hash
+	}
+
+	/// Weight of relaying `headers`, growing with the bytes decoded and hashed. Importing one
+	/// reads its parent and prunes the headers falling out of the kept range.
+	fn eth_headers_weight(headers: &[Vec<u8>]) -> Weight {
+		let bytes = headers.iter().map(Vec::len).sum::<usize>() as Weight;
+		let per_header = T::DbWeight::get().reads_writes(8, 8);
+		per_header.saturating_mul(headers.len() as Weight).saturating_add(PROOF_BYTE_WEIGHT.saturating_mul(bytes))
+	}
+
+	/// Weight of verifying and applying `proofs`, growing with the bytes hashed. Checking the
+	/// confirmations reads up to all kept headers.
+	fn event_proofs_weight(proofs: &[EventProof]) -> Weight {
+		let bytes = proofs.iter()
+			.map(|proof| proof.header.len() + proof.receipt_proof.iter().map(Vec::len).sum::<usize>())
+			.sum::<usize>() as Weight;
+		let per_proof = T::DbWeight::get().reads_writes(ETH_HEADERS_KEPT + 16, 12).saturating_add(SWAP_WEIGHT);
+		per_proof.saturating_mul(proofs.len() as Weight).saturating_add(PROOF_BYTE_WEIGHT.saturating_mul(bytes))
+	}
+
+	/// Swap event of a log proven against a confirmed header.
+	fn verify_event_proof(proof: &EventProof, config: &ProofConfig) -> Result<EventHTLC<T::BlockNumber, T::Balance, T::Hash>, DispatchError> {
+		let hash = eth::decode_header(&proof.header)?.hash;
+		let header = Self::eth_header(hash).ok_or(Error::<T>::UnknownEthHeader)?;
+		ensure!(Self::is_confirmed(&hash, header.number, config.confirmations), Error::<T>::EthHeaderNotConfirmed);
+
+		let receipt = eth::verify_proof(&header.receipts_root, &eth::rlp_index(proof.tx_index), &proof.receipt_proof)
+			.map_err(|_| Error::<T>::InvalidReceiptProof)?;
+		let logs = eth::decode_receipt_logs(&receipt, header.number).map_err(|_| Error::<T>::InvalidReceiptProof)?;
+		let log = logs.get(proof.log_index as usize).ok_or(Error::<T>::InvalidReceiptProof)?;
+
+		let contract_addr = Self::scan_config().map(|config| config.contract_addr).ok_or(Error::<T>::UnknownContract)?;
+		ensure!(eth::decode_hex(&contract_addr).ok() == Some(log.address.to_vec()), Error::<T>::UnknownContract);
+
+		Ok(Self::parse_event(log)?.ok_or(Error::<T>::InvalidEventType)?)
+	}
+
+	/// Update swap states and release funds for an event that reached the threshold.
+	/// Dispatch is not transactional, every check runs before the first write.
+	fn apply_event(htlc: EventHTLC<T::BlockNumber, T::Balance, T::Hash>) -> dispatch_result {
+		let pra_token_addr = Self::pra_token_addr().ok_or("error not valid pra_token_addr")?;
+		let event_block_number = htlc.event_block_number;
+
+		match htlc.event_type {
+			HTLCType::HTLC => {
+				if !<SwapData<T>>::contains_key(htlc.swap_id) && !<SwapStates<T>>::contains_key(htlc.swap_id) {
+					let swap_states_count = Self::swap_states_count();
+					let new_count = swap_states_count.checked_add(1).ok_or("Overflow adding swap_states_count")?;
+					Self::note_eth_block(event_block_number);
+
+					let over_limit = Self::swap_limits().map_or(false, |limits| htlc.out_amount > limits.max_amount);
+					let unknown_receiver = <did::Module<T>>::identity_of(htlc.receiver_addr).is_none();
+					if over_limit || unknown_receiver || !Self::check_solvency(&pra_token_addr, htlc.out_amount) {
+						Self::set_swap_state(htlc.swap_id, HTLCStates::INVALID);
+						<SwapStatesCount>::put(new_count);
+						Self::deposit_event(RawEvent::SwapRejected(htlc.swap_id));
+						return Ok(());
+					}
+
+					let mut htlc = htlc;
+					htlc.htlc_block_number = <system::Module<T>>::block_number();
+					let expiry = Self::eth_expiry(&htlc);
+					let expired = expiry <= Self::eth_expiry_swept();
+					<SwapData<T>>::insert(htlc.swap_id, &htlc);
+					let received = Self::receiver_swaps_count(htlc.receiver_addr);
+					<ReceiverSwaps<T>>::insert(htlc.receiver_addr, received, htlc.swap_id);
+					<ReceiverSwapsCount<T>>::insert(htlc.receiver_addr, received.saturating_add(1));
+					if expired {
+						Self::set_swap_state(htlc.swap_id, HTLCStates::EXPIRED);
+					} else {
+						Self::set_swap_state(htlc.swap_id, HTLCStates::OPEN);
+						<EthExpiries<T>>::mutate(expiry, |ids| ids.push(htlc.swap_id));
+					}
+					<SwapStatesCount>::put(new_count);
+					<EthSwapTotals<T>>::mutate(|totals| {
+						totals.opened = totals.opened.saturating_add(htlc.out_amount);
+						if expired {
+							totals.refunded = totals.refunded.saturating_add(htlc.out_amount);
+						}
+					});
+
+					Self::deposit_event(RawEvent::HTLC(htlc.receiver_addr, htlc.eth_contract_addr.clone(), htlc.htlc_block_number, htlc.expire_height,
+						htlc.random_number_hash, htlc.swap_id, htlc.sender_addr, htlc.out_amount));
+					if expired {
+						Self::deposit_event(RawEvent::Expired(htlc.swap_id));
+					}
+					Self::apply_waiting(htlc.swap_id);
+				} else {
+					Self::note_eth_block(event_block_number);
+					native::error!(target: "swap", "HTLC init swap_id already exists");
+				}
+			},
+			HTLCType::Claimed => {
+				let swap_id = htlc.swap_id;
+				ensure!(<SwapStates<T>>::contains_key(swap_id), Error::<T>::UnknownSwap);
+				let random_number = htlc.random_number_hash;
+				let htlc = <SwapData<T>>::get(&swap_id).ok_or(Error::<T>::UnknownSwap)?;
+				// a claim mined on ethereum before expiry is honoured even if already swept
+				ensure!(Self::is_claimable(&swap_id, event_block_number), Error::<T>::SwapExpired);
+				ensure!(
+					Self::verify_random_number(&random_number, &htlc.random_number_hash, htlc.htlc_timestamp),
+					Error::<T>::InvalidRandomNumber
+				);
+
+				// nothing is written when the claim can't be settled
+				Self::settle_claim(swap_id, htlc, random_number, false)?;
+				Self::note_eth_block(event_block_number);
+			},
+			HTLCType::Refunded => {
+				let swap_id = htlc.swap_id;
+				ensure!(<SwapStates<T>>::contains_key(swap_id), Error::<T>::UnknownSwap);
+				let swap = <SwapData<T>>::get(&swap_id).ok_or(Error::<T>::UnknownSwap)?;
+				Self::note_eth_block(event_block_number);
+				if Self::swap_states(swap_id) == Some(HTLCStates::OPEN) {
+					Self::note_eth_refunded(swap.out_amount);
+				}
+				<SwapData<T>>::remove(&swap_id);
+				Self::set_swap_state(swap_id, HTLCStates::EXPIRED);
+
+				Self::deposit_event(RawEvent::Refund(htlc.receiver_addr, htlc.eth_contract_addr, swap_id, htlc.sender_addr, htlc.random_number_hash));
+			},
+		}
+		Ok(())
+	}
+
+	/// Track the latest ethereum block an applied event was mined in.
+	fn note_eth_block(event_block_number: T::BlockNumber) {
+		if event_block_number > Self::eth_block_number() {
+			if Self::eth_block_number().is_zero() {
+				<EthExpirySwept<T>>::put(event_block_number);
+			}
+			<EthBlockNumber<T>>::put(event_block_number);
+		}
+	}
+
+	/// Pay out a verified claim, holding it while paused, frozen or over the daily cap and
+	/// queueing large claims unless their delay already passed.
+	fn settle_claim(swap_id: T::Hash, htlc: EventHTLC<T::BlockNumber, T::Balance, T::Hash>, random_number: Vec<u8>, delayed: bool) -> dispatch_result {
+		if Self::paused() || Self::frozen_swaps(swap_id) || !Self::within_daily_cap(htlc.out_amount) {
+			<HeldClaims<T>>::insert(swap_id, random_number);
+			Self::deposit_event(RawEvent::ClaimHeld(swap_id));
+			return Ok(());
+		}
+
+		if let Some(limits) = Self::swap_limits() {
+			if !delayed && htlc.out_amount > limits.delay_threshold {
+				let when = <system::Module<T>>::block_number() + limits.release_delay;
+				T::Scheduler::schedule_named(Self::release_id(&swap_id), when, None, 63, Call::release_swap(swap_id).into())
+					.map_err(|_| "error scheduling swap release")?;
+				<QueuedReleases<T>>::insert(swap_id, (when, random_number));
+				Self::deposit_event(RawEvent::ReleaseQueued(swap_id, when));
+				return Ok(());
+			}
+		}
+
+		Self::complete_claim(swap_id, htlc, random_number)
+	}
+
+	fn release_id(swap_id: &T::Hash) -> Vec<u8> {
+		(SWAP_RELEASE_ID, swap_id).encode()
+	}
+
+	/// Index of the current volume bucket.
+	fn volume_bucket() -> T::BlockNumber {
+		let len = (T::VolumeWindow::get() / T::BlockNumber::from(VOLUME_BUCKETS)).max(One::one());
+		<system::Module<T>>::block_number() / len
+	}
+
+	/// Whether releasing `amount` stays within the daily cap.
+	fn within_daily_cap(amount: T::Balance) -> bool {
+		let limits = match Self::swap_limits() {
+			Some(limits) => limits,
+			None => return true,
+		};
+		let oldest = Self::volume_bucket().saturating_sub(T::BlockNumber::from(VOLUME_BUCKETS - 1));
+		let released = Self::released_volume()
+			.iter()
+			.filter(|(bucket, _)| *bucket >= oldest)
+			.fold(T::Balance::from(0u32), |total, (_, amount)| total.saturating_add(*amount));
+		released.saturating_add(amount) <= limits.daily_cap
+	}
+
+	fn note_released(amount: T::Balance) {
+		let bucket = Self::volume_bucket();
+		let oldest = bucket.saturating_sub(T::BlockNumber::from(VOLUME_BUCKETS - 1));
+		<ReleasedVolume<T>>::mutate(|volume| {
+			volume.retain(|(b, _)| *b >= oldest);
+			match volume.last_mut() {
+				Some((b, total)) if *b == bucket => *total = total.saturating_add(amount),
+				_ => volume.push((bucket, amount)),
+			}
+		});
+	}
+
+	/// Pay out a verified claim of an ethereum swap from PraTokenAddr.
+	fn complete_claim(swap_id: T::Hash, htlc: EventHTLC<T::BlockNumber, T::Balance, T::Hash>, random_number: Vec<u8>) -> dispatch_result {
+		let pra_token_addr = Self::pra_token_addr().ok_or("error not valid pra_token_addr")?;
+		Self::transfer_to_did_hash(pra_token_addr, htlc.receiver_addr.clone(), htlc.out_amount)?;
+
+		Self::note_released(htlc.out_amount);
+		let swept = Self::swap_states(swap_id) == Some(HTLCStates::EXPIRED);
+		<EthSwapTotals<T>>::mutate(|totals| {
+			totals.claimed = totals.claimed.saturating_add(htlc.out_amount);
+			if swept {
+				totals.refunded = totals.refunded.saturating_sub(htlc.out_amount);
+			}
+		});
+		<SwapData<T>>::remove(&swap_id);
+		Self::set_swap_state(swap_id, HTLCStates::COMPLETED);
+		<SwapSecrets<T>>::insert(swap_id, (htlc.random_number_hash, random_number.clone()));
+		Self::deposit_event(RawEvent::Claim(htlc.receiver_addr, htlc.eth_contract_addr, swap_id, htlc.sender_addr, random_number));
+		Ok(())
+	}
+
+	/// Ethereum block number an ethereum swap expires at.
+	fn eth_expiry(htlc: &EventHTLC<T::BlockNumber, T::Balance, T::Hash>) -> T::BlockNumber {
+		htlc.event_block_number + T::BlockNumber::from(htlc.expire_height)
+	}
+
+	/// Expire open ethereum swaps up to the latest attested ethereum block, a bounded range and
+	/// at most `limit` swaps per block. A height with more swaps left is swept again next block.
+	/// Returns the heights read and the swaps handled.
+	fn expire_eth_swaps(limit: u32) -> (u32, u32) {
+		let swept = Self::eth_expiry_swept();
+		let target = Self::eth_block_number().min(swept + T::BlockNumber::from(MAX_EXPIRY_SWEEP));
+		let (mut height, mut heights, mut handled) = (swept, 0u32, 0u32);
+		while height < target && handled < limit {
+			let next = height + One::one();
+			let mut swap_ids = <EthExpiries<T>>::take(next);
+			heights += 1;
+			let left = (limit - handled) as usize;
+			let rest = if swap_ids.len() > left { swap_ids.split_off(left) } else { Vec::new() };
+			handled += swap_ids.len() as u32;
+			for swap_id in swap_ids {
+				// a held or queued claim keeps its swap open until released
+				let claimed = <HeldClaims<T>>::contains_key(swap_id) || <QueuedReleases<T>>::contains_key(swap_id);
+				if Self::swap_states(swap_id) == Some(HTLCStates::OPEN) && !claimed {
+					if let Some(swap) = Self::swap_data(swap_id) {
+						Self::note_eth_refunded(swap.out_amount);
+					}
+					Self::set_swap_state(swap_id, HTLCStates::EXPIRED);
+					Self::deposit_event(RawEvent::Expired(swap_id));
+				}
+			}
+			if !rest.is_empty() {
+				<EthExpiries<T>>::insert(next, rest);
+				break;
+			}
+			height = next;
+		}
+		if height > swept {
+			<EthExpirySwept<T>>::put(height);
+		}
+		(heights, handled)
+	}
+
+	/// Weight of `on_initialize` reading `heights` ethereum expiry lists and expiring `swaps` swaps.
+	fn expiry_weight(heights: u32, swaps: u32) -> Weight {
+		let db = T::DbWeight::get();
+		db.reads_writes(3 + heights as Weight, 2 + heights as Weight)
+			.saturating_add(db.reads_writes(5, 6).saturating_add(SWAP_WEIGHT).saturating_mul(swaps as Weight))
+	}
+
+	fn note_eth_refunded(amount: T::Balance) {
+		<EthSwapTotals<T>>::mutate(|totals| totals.refunded = totals.refunded.saturating_add(amount));
+	}
+
+	/// Amount of ethereum swaps PraTokenAddr still has to cover.
+	fn outstanding() -> T::Balance {
+		let totals = Self::eth_swap_totals();
+		totals.opened.saturating_sub(totals.claimed).saturating_sub(totals.refunded)
+	}
+
+	fn free_balance(who: &T::AccountId) -> T::Balance {
+		<pallet_balances::Module<T> as Currency<_>>::free_balance(who)
+	}
+
+	/// Whether PraTokenAddr covers the open ethereum swaps plus `amount`, halting new swaps if not.
+	fn check_solvency(pra_token_addr: &T::AccountId, amount: T::Balance) -> bool {
+		if Self::swaps_halted() {
+			return false;
+		}
+		let required = Self::outstanding().saturating_add(amount);
+		let available = Self::free_balance(pra_token_addr);
+		if available < required {
+			<SwapsHalted>::put(true);
+			Self::deposit_event(RawEvent::SwapsHalted(required, available));
+			return false;
+		}
+		true
+	}
+
+	/// A swap with its state, data and pending release.
+	pub fn swap(swap_id: T::Hash) -> Option<SwapInfo<T::AccountId, T::Hash, T::Balance, T::BlockNumber>> {
+		let state = Self::swap_states(swap_id)?;
+		Some(SwapInfo {
+			swap_id,
+			state,
+			eth: Self::swap_data(swap_id),
+			native: Self::native_swaps(swap_id),
+			frozen: Self::frozen_swaps(swap_id),
+			held: <HeldClaims<T>>::contains_key(swap_id),
+			release_at: Self::queued_releases(swap_id).map(|(when, _)| when),
+		})
+	}
+
+	/// Record the state of a swap, keeping the list of open swaps in step.
+	fn set_swap_state(swap_id: T::Hash, state: HTLCStates) {
+		if state == HTLCStates::OPEN {
+			OpenSwapList::<T>::append(&swap_id);
+		} else {
+			OpenSwapList::<T>::remove(&swap_id);
+		}
+		<SwapStates<T>>::insert(swap_id, state);
+	}
+
+	/// Open swaps of both directions in the order they were opened, following the swap `after`
+	/// or from the oldest one.
+	pub fn open_swaps(after: Option<T::Hash>, limit: u32) -> Vec<SwapInfo<T::AccountId, T::Hash, T::Balance, T::BlockNumber>> {
+		OpenSwapList::<T>::page(after, limit.min(MAX_QUERY_LIMIT) as usize)
+			.into_iter()
+			.filter_map(Self::swap)
+			.collect()
+	}
+
+	/// Ethereum swaps paying the given did hash, oldest first.
+	pub fn swaps_by_receiver(did: T::Hash, offset: u32, limit: u32) -> Vec<SwapInfo<T::AccountId, T::Hash, T::Balance, T::BlockNumber>> {
+		let end = offset.saturating_add(limit.min(MAX_QUERY_LIMIT)).min(Self::receiver_swaps_count(did));
+		(offset..end)
+			.filter_map(|index| Self::receiver_swap(did, index))
+			.filter_map(Self::swap)
+			.collect()
+	}
+
+	/// Event source and scan config of the offchain worker job.
+	pub fn scan_job() -> Option<(EventLogSource, Option<ScanConfig>)> {
+		Self::oc_requests().map(|source| (source, Self::scan_config()))
+	}
+
+	/// Swap totals against the balance of PraTokenAddr.
+	pub fn reconciliation() -> Reconciliation<T::Balance> {
+		Reconciliation {
+			eth: Self::eth_swap_totals(),
+			native: Self::native_swap_totals(),
+			outstanding: Self::outstanding(),
+			available: Self::pra_token_addr().map(|addr| Self::free_balance(&addr)).unwrap_or_default(),
+			halted: Self::swaps_halted(),
+		}
+	}
+
+	/// Refund up to `limit` open native swaps expiring at `now`, moving the rest to the next block.
+	/// Returns the swaps handled.
+	fn expire_native_swaps(now: T::BlockNumber, limit: u32) -> u32 {
+		let mut swap_ids = <NativeExpiries<T>>::take(now);
+		if swap_ids.len() > limit as usize {
+			let rest = swap_ids.split_off(limit as usize);
+			<NativeExpiries<T>>::mutate(now + One::one(), |ids| ids.extend(rest));
+		}
+		let handled = swap_ids.len() as u32;
+		for swap_id in swap_ids {
+			// frozen swaps are refunded by hand once unfrozen
+			if Self::swap_states(swap_id) != Some(HTLCStates::OPEN) || Self::frozen_swaps(swap_id) {
+				continue;
+			}
+			if let Some(swap) = Self::native_swaps(swap_id) {
+				Self::refund_native(swap_id, swap);
+			}
+		}
+		handled
+	}
+
+	fn refund_native(swap_id: T::Hash, swap: NativeHTLC<T::AccountId, T::Balance, T::BlockNumber>) {
+		<pallet_balances::Module<T> as ReservableCurrency<_>>::unreserve(&swap.sender, swap.amount);
+
+		Self::set_swap_state(swap_id, HTLCStates::EXPIRED);
+		<NativeSwapTotals<T>>::mutate(|totals| totals.refunded = totals.refunded.saturating_add(swap.amount));
+		Self::deposit_event(RawEvent::NativeRefund(swap_id, swap.sender, swap.amount));
+	}
+
+	/// Whether sha256(random_number ‖ timestamp) matches random_number_hash, both hex encoded.
+	fn verify_random_number(random_number: &[u8], random_number_hash: &[u8], timestamp: u64) -> bool {
+		let (number, hash) = match (eth::decode_hex(random_number), eth::decode_hex(random_number_hash)) {
+			(Ok(number), Ok(hash)) => (number, hash),
+			_ => return false,
+		};
+		if number.len() != 32 {
+			return false;
+		}
+		Self::verify_preimage(&number, &hash, timestamp)
+	}
+
+	/// Whether sha256(random_number ‖ timestamp) matches random_number_hash.
+	fn verify_preimage(random_number: &[u8], random_number_hash: &[u8], timestamp: u64) -> bool {
+		let mut preimage = random_number.to_vec();
+		preimage.extend_from_slice(&timestamp.to_be_bytes());
+		sp_io::hashing::sha2_256(&preimage)[..] == random_number_hash[..]
+	}
+
+	fn to_balance(val: u128) -> Result<T::Balance, &'static str> {
+		val.try_into()
+			.map_err(|_| "Convert to Balance type overflow")
+	}
+
+	/// Scan the next confirmed range of ethereum blocks and submit the events found.
+	fn scan_events(source: &EventLogSource, config: &ScanConfig) -> Result<(), &'static str> {
+		let endpoint = Self::endpoint(source);
+		let url = endpoint.url()?;
+		if url.is_empty() {
+			return Err("error no event source url");
+		}
+
+		let cursor = StorageValueRef::persistent(SCAN_CURSOR_KEY);
+		let prev = cursor.get::<(Vec<u8>, u64)>();
+		let scanned = match &prev {
+			Some(Some((contract_addr, scanned))) if *contract_addr == config.contract_addr => *scanned,
+			_ => config.start_block.saturating_sub(1),
+		};
+
+		let head = Self::fetch_block_number(source, &endpoint)?;
+		let to = head.saturating_sub(config.confirmations).min(scanned.saturating_add(config.max_step));
+		if to <= scanned {
+			return Ok(());
+		}
+		let from = scanned + 1;
+		native::info!(target: "swap", "scan events from {:?} to {:?}", from, to);
+
+		let mut htlcs = Vec::new();
+		if source.event_type == EVENT_SRC_ETHERSCAN {
+			for topic in config.topics.iter() {
+				let query = Self::with_query(url, &[
+					b"module=logs&action=getLogs&fromBlock=", &Self::to_dec(from),
+					b"&toBlock=", &Self::to_dec(to),
+					b"&address=", &config.contract_addr,
+					b"&topic0=", topic,
+				]);
+				let query = core::str::from_utf8(&query).map_err(|_| "error query is not valid utf8")?;
+				htlcs.extend(Self::parse_logs(Self::http_request_get(&endpoint, query)?)?);
+			}
+		} else if source.event_type == EVENT_SRC_INFURA {
+			let body = Self::get_logs_body(config, from, to);
+			htlcs.extend(Self::parse_logs(Self::http_request_post(&endpoint, url, &body)?)?);
+		} else {
+			return Err("error invalid event source type");
+		}
+
+		// move on only once the events are submitted, a range submitted twice by concurrent
+		// workers is harmless as each authority's attestation counts once
+		Self::submit_htlcs(htlcs)?;
+		let _ = cursor.mutate(|current: Option<Option<(Vec<u8>, u64)>>| {
+			if current == prev {
+				Ok((config.contract_addr.clone(), to))
+			} else {
+				Err(())
+			}
+		});
+		Ok(())
+	}
+
+	/// Endpoint of the event source, the one in offchain local storage if set.
+	fn endpoint(source: &EventLogSource) -> http::Endpoint {
+		http::Endpoint::load(ENDPOINT_KEY).unwrap_or_else(|| http::Endpoint {
+			url: source.event_url.clone(),
+			headers: Vec::new(),
+		})
+	}
+
+	/// Latest ethereum block number reported by the event source.
+	fn fetch_block_number(source: &EventLogSource, endpoint: &http::Endpoint) -> Result<u64, &'static str> {
+		let url = endpoint.url()?;
+		let res = if source.event_type == EVENT_SRC_ETHERSCAN {
+			let query = Self::with_query(url, &[b"module=proxy&action=eth_blockNumber"]);
+			let query = core::str::from_utf8(&query).map_err(|_| "error query is not valid utf8")?;
+			Self::http_request_get(endpoint, query)?
+		} else {
+			let body = br#"{"jsonrpc":"2.0","id":1,"method":"eth_blockNumber","params":[]}"#.to_vec();
+			Self::http_request_post(endpoint, url, &body)?
+		};
+
+		eth::parse_quantity_response(&res)
+	}
+
+	/// `eth_getLogs` request body for the configured contract and topics.
+	fn get_logs_body(config: &ScanConfig, from: u64, to: u64) -> Vec<u8> {
+		let mut body = br#"{"jsonrpc":"2.0","id":1,"method":"eth_getLogs","params":[{"fromBlock":""#.to_vec();
+		body.extend(Self::to_quantity(from));
+		body.extend_from_slice(br#"","toBlock":""#);
+		body.extend(Self::to_quantity(to));
+		body.extend_from_slice(br#"","address":""#);
+		body.extend_from_slice(&config.contract_addr);
+		body.extend_from_slice(br#"","topics":[["#);
+		for (i, topic) in config.topics.iter().enumerate() {
+			if i > 0 {
+				body.push(b',');
+			}
+			body.push(b'"');
+			body.extend_from_slice(topic);
+			body.push(b'"');
+		}
+		body.extend_from_slice(b"]]}]}");
+		body
+	}
+
+	/// Whether the url carries credentials: userinfo, a key or token query parameter, or a
+	/// key shaped path segment like infura's `/v3/<project-id>`.
+	fn has_credentials(url: &[u8]) -> bool {
+		let url = url.to_ascii_lowercase();
+		let rest = match url.windows(3).position(|w| w == b"://") {
+			Some(i) => &url[i + 3..],
+			None => &url[..],
+		};
+		let (location, query) = match rest.iter().position(|c| *c == b'?') {
+			Some(i) => (&rest[..i], &rest[i + 1..]),
+			None => (rest, &[][..]),
+		};
+		let mut parts = location.split(|c| *c == b'/');
+		let authority = parts.next().unwrap_or_default();
+
+		let userinfo = authority.contains(&b'@');
+		let key_param = [&b"key="[..], b"token=", b"secret="]
+			.iter()
+			.any(|name| query.windows(name.len()).any(|w| w == *name));
+		let key_segment = parts.any(|segment| {
+			segment.len() >= 16
+				&& segment.iter().any(u8::is_ascii_digit)
+				&& segment.iter().all(|c| c.is_ascii_alphanumeric() || *c == b'-' || *c == b'_')
+		});
+		userinfo || key_param || key_segment
+	}
+
+	/// Append query parameters to an etherscan url.
+	fn with_query(url: &str, params: &[&[u8]]) -> Vec<u8> {
+		let mut query = url.as_bytes().to_vec();
+		query.push(if url.contains('?') { b'&' } else { b'?' });
+		for param in params {
+			query.extend_from_slice(param);
+		}
+		query
+	}
+
+	fn to_dec(mut n: u64) -> Vec<u8> {
+		let mut digits = Vec::new();
+		loop {
+			digits.push(b'0' + (n % 10) as u8);
+			n /= 10;
+			if n == 0 {
+				break;
+			}
+		}
+		digits.reverse();
+		digits
+	}
+
+	/// Hex quantity as used by ethereum json rpc, e.g. `0x1f`.
+	fn to_quantity(mut n: u64) -> Vec<u8> {
+		let mut digits = Vec::new();
+		loop {
+			digits.push(b"0123456789abcdef"[(n % 16) as usize]);
+			n /= 16;
+			if n == 0 {
+				break;
+			}
+		}
+		digits.extend_from_slice(b"x0");
+		digits.reverse();
+		digits
+	}
+
+	/// Local `orin` keys whose account is an oracle authority.
+	fn authority_keys() -> Vec<T::Public> {
+		<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| {
+				let generic = <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic::from(key);
+				let public: T::Public = generic.into();
+				public
+			})
+			.filter(|public| Self::is_authority(&public.clone().into_account()))
+			.collect()
+	}
+
+	/// Submit parsed events in a transaction signed by the local authority keys.
+	fn submit_htlcs(htlcs: Vec<EventHTLC<T::BlockNumber, T::Balance, T::Hash>>) -> Result<(), &'static str> {
+		if htlcs.is_empty() {
+			return Ok(());
+		}
+		let keys = Self::authority_keys();
+		if keys.is_empty() {
+			native::warn!(target: "swap", "no local oracle authority key to sign events");
+			return Ok(());
+		}
+
+		for chunk in htlcs.chunks(MAX_EVENTS_PER_CALL as usize) {
+			let results = Signer::<T, T::AuthorityId>::all_accounts()
+				.with_filter(keys.clone())
+				.send_signed_transaction(|_account| Call::update_enevt_htlc(chunk.to_vec()));
+			for (account, result) in results {
+				if result.is_err() {
+					native::error!(target: "swap", "failed to submit events signed by {:?}", account.id);
+					return Err("Unable to submit signed transaction.");
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Swap events in an `eth_getLogs` response, skipping other and removed logs.
+	fn parse_logs(res: Vec<u8>) -> Result<Vec<EventHTLC<T::BlockNumber, T::Balance, T::Hash>>, &'static str> {
+		let mut htlcs = Vec::new();
+		for log in eth::parse_logs_response(&res)?.iter().filter(|log| !log.removed) {
+			match Self::parse_event(log) {
+				Ok(Some(htlc)) => htlcs.push(htlc),
+				Ok(None) => native::error!(target: "swap", "not valid event signature {:?}", log.topics.first()),
+				Err(e) => native::error!(target: "swap", "parse event {:?} err {:?}", log.transaction_hash, e),
+			}
+		}
+		Ok(htlcs)
+	}
+
+	/// Decode a HTLC, Claimed or Refunded log of the swap contract.
+	fn parse_event(log: &eth::Log) -> Result<Option<EventHTLC<T::BlockNumber, T::Balance, T::Hash>>, &'static str> {
+		let event_type = if log.is_event(&eth::decode_word(EVENT_SIG_HTLC.as_bytes())?) {
+			HTLCType::HTLC
+		} else if log.is_event(&eth::decode_word(EVENT_SIG_CLAIM.as_bytes())?) {
+			HTLCType::Claimed
+		} else if log.is_event(&eth::decode_word(EVENT_SIG_REFUND.as_bytes())?) {
+			HTLCType::Refunded
+		} else {
+			return Ok(None);
+		};
+		ensure!(log.topics.len() == 4, "error event topics mismatch");
+
+		let data = eth::AbiData::new(&log.data);
+		let event_block_num: u32 = log.block_number.try_into().map_err(|_| "error event block number overflow")?;
+		// data words: HTLC (randomNumberHash, timestamp, expireHeight, outAmount, praAmount, receiverAddr),
+		// Claimed (randomNumber, receiverAddr), Refunded (randomNumberHash, _, receiverAddr)
+		let (random_number_hash, receiver_did, htlc_timestamp, expire_height, out_amount) = match event_type {
+			HTLCType::HTLC => {
+				let expire_block_num: u32 = data.uint(2)?.try_into().map_err(|_| "error expire height overflow")?;
+				let event_out_amount = data.uint(3)?;
+				ensure!(
+					event_out_amount > 0 && event_out_amount == data.uint(4)?,
+					"not valid out_amount or pra_amount"
+				);
+				//Important: precision from eth contract is 18, substrate precision is 15
+				let out_balance = Self::to_balance(event_out_amount / 1000u128)
+					.map_err(|_| "error parse event_out_amount to balance")?;
+				(
+					data.bytes32(0)?,
+					data.bytes(5)?,
+					data.uint(1)?.try_into().map_err(|_| "error htlc timestamp overflow")?,
+					expire_block_num.checked_sub(event_block_num).ok_or("error expire height before event")?,
+					out_balance,
+				)
+			},
+			HTLCType::Claimed => (data.bytes32(0)?, data.bytes(1)?, 0, 0, T::Balance::from(0u32)),
+			HTLCType::Refunded => (data.bytes32(0)?, data.bytes(2)?, 0, 0, T::Balance::from(0u32)),
+		};
+
+		let mut eth_contract_addr = STR_PREFIX.as_bytes().to_vec();
+		eth_contract_addr.extend(eth::encode_hex(&log.address));
+
+		Ok(Some(EventHTLC {
+			eth_contract_addr,
+			event_block_number: T::BlockNumber::from(event_block_num),
+			htlc_block_number: <system::Module<T>>::block_number(),
+			out_amount,
+			expire_height,
+			random_number_hash: eth::encode_hex(&random_number_hash),
+			swap_id: T::Hashing::hash(&eth::encode_hex(&log.topics[3])),
+			htlc_timestamp,
+			sender_addr: eth::encode_hex(&log.topics[1]),
+			sender_chain_type: HTLCChain::ETHMain,
+			receiver_addr: Self::did_hash(receiver_did)?,
+			receiver_chain_type: HTLCChain::PRM,
+			recipient_addr: eth::encode_hex(&log.topics[2]),
+			event_type,
+		}))
+	}
+
+	fn http_request_get(endpoint: &http::Endpoint, uri: &str) -> Result<Vec<u8>, &'static str> {
+		let request = endpoint.with_headers(http::Request::get(uri))?;
+		Ok(request.timeout(HTTP_TIMEOUT).retries(HTTP_RETRIES, http::DEFAULT_BACKOFF).send()?)
+	}
+
+	fn http_request_post(endpoint: &http::Endpoint, url: &str, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+		let request = endpoint.with_headers(http::Request::post(url, data))?;
+		Ok(request.timeout(HTTP_TIMEOUT).retries(HTTP_RETRIES, http::DEFAULT_BACKOFF).send()?)
+	}
+
+	//Helper that confirms whether the given `AccountId` has auth
+	fn is_authority(who: &T::AccountId) -> bool {
+		Self::authorities().contains(who)
+	}
+
+	//if HTLC claimable at ethereum block number `eth_block`
+	fn is_claimable(swap_id: &T::Hash, eth_block: T::BlockNumber) -> bool {
+		match Self::swap_data(swap_id) {
+			Some(swap) => eth_block < Self::eth_expiry(&swap),
+			None => false,
+		}
+	}
+
+	fn transfer_to_did_hash(
+		sender: T::AccountId,
+		receiver_did: T::Hash,
+		amount: T::Balance,
+	) -> dispatch_result {
+		ensure!(!Self::paused(), Error::<T>::Paused);
+		let receiver = <did::Module<T>>::identity_of(receiver_did.clone());
+		ensure!(receiver.is_some(), "error not valid receiver did");
+
+		let receiver = receiver.unwrap();
+		<pallet_balances::Module<T> as Currency<_>>::transfer(
+			&sender,
+			&receiver,
+			amount,
+			ExistenceRequirement::KeepAlive,
+		)?;
+		Self::deposit_event(RawEvent::TransferToDid(
+			sender,
+			receiver,
+			receiver_did,
+			amount,
+		));
+		Ok(())
+	}
+
+	//did hex_str
+	fn parse_did(did: &Vec<u8>) -> Result<T::AccountId, &'static str> {
+		let receiver_did_hash = Self::did_hash(did)?;
+		<did::Module<T>>::identity_of(receiver_did_hash).ok_or("error parse did failed")
+	}
+
+	/// Key of a `did:pra:<base58>` did in the did pallet.
+	fn did_hash(did: &[u8]) -> Result<T::Hash, &'static str> {
+		let data = core::str::from_utf8(did).map_err(|_| "error not valid utf8 did")?;
+
+		let vecs: Vec<&str> = data.split(":").collect();
+		ensure!(
+			vecs.len() == 3 && vecs[2].len() > 0,
+			"error not found valid did"
+		);
+
+		let did_ele_hex = Self::from_base58(vecs[2]).map_err(|_| "error Bad Base58")?;
+		Ok(T::Hashing::hash(&did_ele_hex))
+	}
+
+	// Convert the base58 str to Vec<u8>
+	fn from_base58(data: &str) -> Result<Vec<u8>, &'static str> {
+		let radix = 58u32.to_biguint().unwrap();
+		let mut x: BigUint = Zero::zero();
+		let mut rad_mult: BigUint = One::one();
+
+		for (idx, &byte) in data.as_bytes().iter().enumerate().rev() {
+			let first_idx = B_ALPHA
+				.iter()
+				.enumerate()
+				.find(|x| *x.1 == byte)
+				.map(|x| x.0);
+			match first_idx {
+				Some(i) => {
+					x = x + i.to_biguint().unwrap() * &rad_mult;
+				}
+				None => return Err("InvalidBase58Byte"),
+			}
+
+			rad_mult = &rad_mult * &radix;
+		}
+
+		let mut r = Vec::new();
+		for _ in data.as_bytes().iter().take_while(|&x| *x == B_ALPHA[0]) {
+			r.push(0);
+		}
+		if x > Zero::zero() {
+			// TODO: use append when it becomes stable
+			r.extend(x.to_bytes_be());
+		}
+		Ok(r)
+	}
+}
@@ -1,22 +1,26 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+mod tests;
+
 use codec::{Decode, Encode};
 use frame_support::{debug, decl_error, decl_event, decl_module, decl_storage, ensure};
-use frame_system::{self as system, ensure_signed};
+use frame_system::{
+	self as system, ensure_root, ensure_signed,
+	offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer},
+};
 use num_traits::float::FloatCore;
 use simple_json::{self, json::JsonValue};
-use sp_core::{
-	crypto::KeyTypeId,
-	offchain::{Duration, HttpRequestId, HttpRequestStatus},
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{
+	app_crypto::RuntimeAppPublic, offchain::storage::StorageValueRef, traits::IdentifyAccount, DispatchResult,
 };
-use sp_runtime::DispatchResult;
 use sp_std::{
 	convert::{Into, TryInto},
 	prelude::*,
 	result::Result,
 	vec::Vec,
 };
-use utilities::FixedU128;
+use utilities::{http, FixedU128};
 
 pub type Price = FixedU128;
 pub type CurrencyId = u32;
@@ -33,45 +37,67 @@ pub struct PriceValue<Moment, AccountId> {
 	timestamp: Moment,
 }
 
+pub const CURRENCY_USD: CurrencyId = 0;
+pub const CURRENCY_PRM: CurrencyId = 1;
+pub const CURRENCY_BTC: CurrencyId = 2;
+
 //  automates offchain fetching every certain blocks. Set 0 disable this feature.
 pub const BLOCK_DURATION: u64 = 5;
 pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"ofpf");
 
 pub mod crypto {
-	pub use super::KEY_TYPE;
-	use sp_runtime::app_crypto::{app_crypto, sr25519};
-	app_crypto!(sr25519, KEY_TYPE);
+	use frame_system::offchain::AppCrypto;
+	use sp_runtime::{MultiSignature, MultiSigner};
+
+	mod app_sr25519 {
+		use sp_runtime::app_crypto::{app_crypto, sr25519};
+		app_crypto!(sr25519, super::super::KEY_TYPE);
+
+		impl From<Signature> for sp_runtime::AnySignature {
+			fn from(sig: Signature) -> Self {
+				sr25519::Signature::from(sig).into()
+			}
+		}
+	}
+
+	pub type AuthorityId = app_sr25519::Public;
+
+	/// Signs price submissions with the local `ofpf` keys.
+	pub struct AuthId;
+
+	impl AppCrypto<MultiSigner, MultiSignature> for AuthId {
+		type RuntimeAppPublic = AuthorityId;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
 }
 
-pub const FETCHED_JOBS: [(&[u8], &[u8], &[u8]); 4] = [
-	(
-		b"BTC",
-		b"coincap",
-		b"https://api.coincap.io/v2/assets/bitcoin",
-	),
-	(
-		b"BTC",
-		b"cryptocompare",
-		b"https://min-api.cryptocompare.com/data/price?fsym=BTC&tsyms=USD",
-	),
-	(
-		b"PRM",
-		b"coinmarketcap",
-		b"https://pro-api.coinmarketcap.com/v1/cryptocurrency/quotes/latest?id=2275",
-	),
-	(
-		b"PRM",
-		b"coingecko",
-		b"https://api.coingecko.com/api/v3/simple/price?ids=prochain&vs_currencies=usd",
-	),
-];
-
-const KEY_ID: &'static str = "id";
-const KEY_PRICE_USD: &'static str = "priceUsd";
-const CMC_KEY: &'static str = "X-CMC_PRO_API_KEY";
-const CMC_KEY_VALUE: &'static str = "20a084fd-afdd-4c81-8e95-08868a45fcaf";
+/// A price feed fetched by the offchain worker.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct PriceSource {
+	pub symbol: Vec<u8>,
+	/// Parser of the response: coincap, cryptocompare, coinmarketcap or coingecko.
+	pub source: Vec<u8>,
+	pub endpoint: http::Endpoint,
+}
 
-pub trait Trait: system::Trait + pallet_timestamp::Trait {
+/// Offchain local storage key of the `Vec<PriceSource>` fetched by the offchain worker.
+/// Set with the `offchain_localStorageSet` RPC or the node's `--offchain-source` file.
+pub const SOURCES_KEY: &[u8] = b"prices::sources";
+
+const HTTP_RETRIES: u32 = 2;
+
+const KEY_DATA: &'static str = "data";
+const KEY_SYMBOL: &'static str = "symbol";
+const KEY_PRICE_USD: &'static str = "priceUsd";
+const KEY_QUOTE: &'static str = "quote";
+const KEY_USD: &'static str = "USD";
+const KEY_PRICE: &'static str = "price";
+const KEY_USD_LOWER: &'static str = "usd";
+
+pub trait Trait: system::Trait + pallet_timestamp::Trait + CreateSignedTransaction<Call<Self>> {
+	/// Crypto of the `ofpf` keys signing price submissions.
+	type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 	type Call: From<Call<Self>>;
@@ -80,28 +106,34 @@ pub trait Trait: system::Trait + pallet_timestamp::Trait {
 decl_storage! {
 	trait Store for Module<T: Trait> as Prices {
 		/// Current set of keys that may feed data
-		pub Authorities get(authorities) config(): Option<T::AccountId>;
+		pub Authorities get(fn authorities) config(): Option<T::AccountId>;
 
 		/// The currency_id price storage
-		pub Values get(values): map CurrencyId => Option<PriceValue<T::Moment, T::AccountId>>;
+		pub Values get(fn values): map hasher(twox_64_concat) CurrencyId => Option<PriceValue<T::Moment, T::AccountId>>;
 	}
 }
 
 decl_error! {
 	pub enum Error for Module<T: Trait> {
 		NoPermission,
+		/// prices are quoted in USD only
+		UnsupportedQuote,
+		/// a price must be positive
+		ZeroPrice,
 	}
 }
 
 decl_event!(
 	pub enum Event<T>
 	where
-        <T as pallet_timestamp::Trait>::Moment,
-        <T as system::Trait>::AccountId,
-    {
-        ///currency_id, quote_currency_id, price, who, timestamp
-        NewPrice(CurrencyId, CurrencyId, Price, AccountId, Moment),
-    }
+		<T as pallet_timestamp::Trait>::Moment,
+		<T as system::Trait>::AccountId,
+	{
+		///currency_id, quote_currency_id, price, who, timestamp
+		NewPrice(CurrencyId, CurrencyId, Price, AccountId, Moment),
+		/// New account feeding prices.
+		AuthorityChanged(AccountId),
+	}
 );
 
 decl_module! {
@@ -110,9 +142,20 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		#[weight = 0]
+		fn set_authority(origin, who: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+			<Authorities<T>>::put(&who);
+			Self::deposit_event(RawEvent::AuthorityChanged(who));
+			Ok(())
+		}
+
+		#[weight = 0]
 		fn feed_value(origin, currency_id: CurrencyId, quote_currency_id: CurrencyId, price: Price) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			ensure!(Self::can_feed_data(&sender), Error::<T>::NoPermission);
+			ensure!(quote_currency_id == CURRENCY_USD && currency_id != CURRENCY_USD, Error::<T>::UnsupportedQuote);
+			ensure!(!price.is_zero(), Error::<T>::ZeroPrice);
 
 			let now = <pallet_timestamp::Module<T>>::get();
 
@@ -131,17 +174,10 @@ decl_module! {
 		}
 
 		fn offchain_worker(now: T::BlockNumber) {
-			if BLOCK_DURATION > 0 && (TryInto::<u64>::try_into(now).ok().unwrap()) % BLOCK_DURATION == 0 {
-				for (sym, src, url) in FETCHED_JOBS.iter() {
-					let symbol = core::str::from_utf8(sym).unwrap();
-					let src_str = core::str::from_utf8(src).unwrap();
-					let url_str = core::str::from_utf8(src).unwrap();
-
-					if let Ok(price) = Self::fetch_price(symbol, src_str, url_str) {
-						debug::info!("fetch price OK: {:?} {:>}", &price, &url_str);
-					} else {
-						debug::error!("fetch price error");
-					}
+			let now = TryInto::<u64>::try_into(now).unwrap_or_default();
+			if BLOCK_DURATION > 0 && now % BLOCK_DURATION == 0 {
+				if let Err(e) = Self::offchain_prices(now / BLOCK_DURATION) {
+					debug::error!("offchain prices error: {:?}", e);
 				}
 			}
 		}
@@ -154,160 +190,218 @@ impl<T: Trait> Module<T> {
 		auth.is_some() && auth.unwrap() == who.clone()
 	}
 
-	fn get_price(base_currency_id: CurrencyId, quote_currency_id: CurrencyId) -> Option<Price> {
-		let base = Self::values(base_currency_id)?;
-		if base.quote_currency_id == quote_currency_id {
-			return Some(base.price);
-		} else {
-			let quote = Self::values(quote_currency_id)?;
-			quote.price.checked_div(&(base.price))
+	/// Price of `currency_id` in USD and when it was fed.
+	fn usd_price(currency_id: CurrencyId) -> Option<(Price, Option<T::Moment>)> {
+		if currency_id == CURRENCY_USD {
+			return Some((FixedU128::from_natural(1), None));
 		}
+		let value = Self::values(currency_id)?;
+		Some((value.price, Some(value.timestamp)))
 	}
 
-	fn fetch_price(symbol: &str, src: &str, url: &str) -> Result<Price, &'static str> {
-		debug::info!("fetch price: {:?}:{:?}:{:?}", &symbol, &src, &url);
+	/// Units of `quote_currency_id` paid for one unit of `base_currency_id`.
+	pub fn get_price(base_currency_id: CurrencyId, quote_currency_id: CurrencyId) -> Option<Price> {
+		Self::get_price_with_timestamp(base_currency_id, quote_currency_id).map(|(price, _)| price)
+	}
 
-		let mut header: Option<(&str, &str)> = None;
+	/// `get_price` together with the time of the oldest value it was derived from.
+	pub fn get_price_with_timestamp(base_currency_id: CurrencyId, quote_currency_id: CurrencyId) -> Option<(Price, T::Moment)> {
+		let (base, base_at) = Self::usd_price(base_currency_id)?;
+		let (quote, quote_at) = Self::usd_price(quote_currency_id)?;
+		let timestamp = match (base_at, quote_at) {
+			(Some(base_at), Some(quote_at)) => base_at.min(quote_at),
+			(Some(fed_at), None) | (None, Some(fed_at)) => fed_at,
+			(None, None) => return None,
+		};
+		Some((base.checked_div(&quote)?, timestamp))
+	}
 
-		if src.as_bytes() == b"coinmarketcap" {
-			header = Some((CMC_KEY, CMC_KEY_VALUE));
+	/// Currency fed for a price source symbol.
+	fn currency_id(symbol: &[u8]) -> Option<CurrencyId> {
+		match symbol {
+			b"PRM" => Some(CURRENCY_PRM),
+			b"BTC" => Some(CURRENCY_BTC),
+			_ => None,
 		}
-		let res = Self::http_request_get(&url, header);
-		if let Ok(buf) = res {
-			let json_str = core::str::from_utf8(&buf).map_err(|_| "res from_utf8 error")?;
-			let json_val: JsonValue =
-				simple_json::parse_json(&json_str).map_err(|_| "JSON res parsing error")?;
-
-			let price: Price = match src {
-				src_t if src_t.as_bytes() == b"coincap" => {
-					Self::parse_from_coincap(symbol, json_val)
-						.map_err(|_| "parse_from_coincap error")
-				}
-				src_t if src_t.as_bytes() == b"cryptocompare" => {
-					Self::parse_from_cryptocompare(json_val)
-						.map_err(|_| "parse_from_cryptocompare error")
-				}
-				src_t if src_t.as_bytes() == b"coinmarketcap" => {
-					Self::parse_from_cmc(symbol, json_val).map_err(|_| "parse_from_cmc error")
-				}
-				src_t if src_t.as_bytes() == b"coingecko" => {
-					Self::parse_from_coingecko(symbol, json_val)
-						.map_err(|_| "parse_from_coingecko error")
-				}
-				_ => Err("error Unknown src"),
-			}?;
-			return Ok(price);
-		}
-		Err("error http_request_get failed")
 	}
 
-	fn parse_from_coincap(symbol: &str, json_val: JsonValue) -> Result<Price, &'static str> {
-		debug::info!("parse_from_coincap: {:?}", &json_val);
-
-		let mut id = Vec::new();
-		let mut price_usd = Vec::new();
-
-		if json_val.get_object().len() > 0 {
-			let data = json_val.get_object()[0].1.get_object();
-
-			data.iter()
-				.filter(|(k, _)| {
-					let key: Vec<u8> = k.iter().map(|c| *c as u8).collect();
-					KEY_ID.as_bytes().to_vec() == key || KEY_PRICE_USD.as_bytes().to_vec() == key
-				})
-				.for_each(|(k, v)| {
-					let vec_of_u8s: Vec<u8> = k.iter().map(|c| *c as u8).collect();
-					let key = core::str::from_utf8(&vec_of_u8s).unwrap();
-
-					if key == KEY_ID {
-						if let JsonValue::String(obj) = v {
-							id = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					} else if key == KEY_PRICE_USD {
-						if let JsonValue::String(obj) = v {
-							price_usd = obj.iter().map(|c| *c as u8).collect::<Vec<u8>>();
-						}
-					}
-				});
-
-			let id_str = core::str::from_utf8(&id);
-			if id_str.is_err() || id_str.unwrap() != symbol {
-				return Err("error parse_from_coincap, symbol not match");
-			}
+	/// Price jobs configured in offchain local storage.
+	fn price_sources() -> Vec<PriceSource> {
+		StorageValueRef::persistent(SOURCES_KEY).get::<Vec<PriceSource>>().flatten().unwrap_or_default()
+	}
 
-			let val_f64: f64 = core::str::from_utf8(&price_usd[..])
-				.map_err(|_| "error value_str not valid utf8")?
-				.parse::<f64>()
-				.map_err(|_| "fetch_price_from_coincap: val_u8 parsing to f64 error")?;
+	/// Fetch the average price of one symbol, taking turns by `round`, and submit it signed by
+	/// the local key of the price authority. Nodes without that key fetch nothing.
+	fn offchain_prices(round: u64) -> Result<(), &'static str> {
+		let keys = Self::authority_keys();
+		if keys.is_empty() {
+			debug::warn!("no local price authority key");
+			return Ok(());
+		}
 
-			//Note: precision is 8
-			let val_u128: u128 = (val_f64 * 100000000.).round() as u128;
-			return Ok(FixedU128::from_natural(val_u128));
+		let sources = Self::price_sources();
+		let mut symbols: Vec<&[u8]> = sources.iter().map(|source| &source.symbol[..]).collect();
+		symbols.sort();
+		symbols.dedup();
+		if symbols.is_empty() {
+			return Ok(());
 		}
-		Err("error parse_from_coincap not valid json_val")
+		let symbol = symbols[(round % symbols.len() as u64) as usize];
+		let currency_id = Self::currency_id(symbol).ok_or("error unknown symbol")?;
+
+		let prices = sources.iter()
+			.filter(|source| source.symbol == symbol)
+			.filter_map(|source| match Self::fetch_price(source) {
+				Ok(price) => {
+					debug::info!("fetch price OK: {:?} {:?}", &price, &source.source);
+					Some(price)
+				},
+				Err(e) => {
+					debug::error!("fetch price error: {:?}", e);
+					None
+				},
+			})
+			.collect();
+		let price = Self::average_prices(prices).ok_or("error no price fetched")?;
+
+		Self::submit_price(keys, currency_id, price)
 	}
 
-	fn parse_from_cryptocompare(json_val: JsonValue) -> Result<Price, &'static str> {
-		debug::info!("parse_from_cryptocompare: {:?}", &json_val);
+	/// Local `ofpf` keys of the account allowed to feed prices.
+	fn authority_keys() -> Vec<T::Public> {
+		<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| {
+				let generic = <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic::from(key);
+				let public: T::Public = generic.into();
+				public
+			})
+			.filter(|public| Self::can_feed_data(&public.clone().into_account()))
+			.collect()
+	}
 
-		let val_f64: f64 = json_val.get_object()[0].1.get_number_f64();
+	fn submit_price(keys: Vec<T::Public>, currency_id: CurrencyId, price: Price) -> Result<(), &'static str> {
+		let results = Signer::<T, T::AuthorityId>::all_accounts()
+			.with_filter(keys)
+			.send_signed_transaction(|_account| Call::feed_value(currency_id, CURRENCY_USD, price));
+		if results.iter().any(|(_, result)| result.is_err()) {
+			return Err("Unable to submit signed transaction.");
+		}
+		Ok(())
+	}
 
-		//Note: precision is 8
-		let val_u128: u128 = (val_f64 * 100000000.).round() as u128;
+	fn fetch_price(source: &PriceSource) -> Result<Price, &'static str> {
+		let symbol = core::str::from_utf8(&source.symbol).map_err(|_| "error symbol not valid utf8")?;
+		debug::info!("fetch price: {:?}:{:?}", &symbol, &source.source);
+
+		let buf = Self::http_request_get(&source.endpoint)?;
+		let json_str = core::str::from_utf8(&buf).map_err(|_| "res from_utf8 error")?;
+		let json_val: JsonValue =
+			simple_json::parse_json(&json_str).map_err(|_| "JSON res parsing error")?;
+
+		let value = match &source.source[..] {
+			b"coincap" => Self::parse_from_coincap(symbol, &json_val),
+			b"cryptocompare" => Self::parse_from_cryptocompare(&json_val),
+			b"coinmarketcap" => Self::parse_from_cmc(symbol, &json_val),
+			b"coingecko" => Self::parse_from_coingecko(&json_val),
+			_ => Err("error Unknown src"),
+		}?;
+		Self::to_price(value)
+	}
 
-		Ok(FixedU128::from_natural(val_u128))
+	/// `{"data":{"symbol":"BTC","priceUsd":"9000.12",..}}`
+	fn parse_from_coincap(symbol: &str, json_val: &JsonValue) -> Result<f64, &'static str> {
+		let data = field(json_val, KEY_DATA).ok_or("error parse_from_coincap no data")?;
+		if field(data, KEY_SYMBOL).and_then(string) != Some(symbol.as_bytes().to_vec()) {
+			return Err("error parse_from_coincap, symbol not match");
+		}
+
+		let price_usd = field(data, KEY_PRICE_USD).and_then(string).ok_or("error parse_from_coincap no priceUsd")?;
+		core::str::from_utf8(&price_usd)
+			.map_err(|_| "error value_str not valid utf8")?
+			.parse::<f64>()
+			.map_err(|_| "fetch_price_from_coincap: val_u8 parsing to f64 error")
+	}
+
+	/// `{"USD":9000.12}`
+	fn parse_from_cryptocompare(json_val: &JsonValue) -> Result<f64, &'static str> {
+		field(json_val, KEY_USD).and_then(number).ok_or("error parse_from_cryptocompare no USD")
 	}
 
-	fn parse_from_cmc(symbol: &str, json_val: JsonValue) -> Result<Price, &'static str> {
-		debug::info!("parse_from_cmc: {:?}", &json_val);
+	/// `{"data":{"2275":{"symbol":"PRM","quote":{"USD":{"price":0.01,..}},..}}}`
+	fn parse_from_cmc(symbol: &str, json_val: &JsonValue) -> Result<f64, &'static str> {
+		let data = field(json_val, KEY_DATA).and_then(first_value).ok_or("error parse_from_cmc no data")?;
+		if field(data, KEY_SYMBOL).and_then(string) != Some(symbol.as_bytes().to_vec()) {
+			return Err("error parse_from_cmc, symbol not match");
+		}
 
-		Err("error parse_from_cmc failed")
+		field(data, KEY_QUOTE)
+			.and_then(|quote| field(quote, KEY_USD))
+			.and_then(|usd| field(usd, KEY_PRICE))
+			.and_then(number)
+			.ok_or("error parse_from_cmc no USD price")
 	}
 
-	fn parse_from_coingecko(symbol: &str, json_val: JsonValue) -> Result<Price, &'static str> {
-		debug::info!("parse_from_coingecko: {:?}", &json_val);
+	/// `{"prochain":{"usd":0.01}}`
+	fn parse_from_coingecko(json_val: &JsonValue) -> Result<f64, &'static str> {
+		first_value(json_val)
+			.and_then(|coin| field(coin, KEY_USD_LOWER))
+			.and_then(number)
+			.ok_or("error parse_from_coingecko no usd")
+	}
 
-		Err("error parse_from_coingecko failed")
+	fn to_price(value: f64) -> Result<Price, &'static str> {
+		if !value.is_finite() || value <= 0. {
+			return Err("error price not positive");
+		}
+		//Note: precision is 8
+		let val_u128: u128 = (value * 100000000.).round() as u128;
+		Ok(FixedU128::from_rational(val_u128, 100000000u128))
 	}
 
-	fn average_prices(prices: Vec<u128>) -> Option<Price> {
+	fn average_prices(prices: Vec<Price>) -> Option<Price> {
 		if prices.len() == 0 {
 			return None;
 		}
 
-		Some(FixedU128::from_natural(0))
+		let count = FixedU128::from_natural(prices.len() as u128);
+		prices.iter()
+			.try_fold(FixedU128::zero(), |sum, price| sum.checked_add(price))?
+			.checked_div(&count)
 	}
 
-	fn http_request_get(uri: &str, header: Option<(&str, &str)>) -> Result<Vec<u8>, &'static str> {
-		let id: HttpRequestId = sp_io::offchain::http_request_start("GET", uri, &[0]).unwrap();
-		let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(10_000));
+	fn http_request_get(endpoint: &http::Endpoint) -> Result<Vec<u8>, &'static str> {
+		let request = endpoint.with_headers(http::Request::get(endpoint.url()?))?;
+		Ok(request.retries(HTTP_RETRIES, http::DEFAULT_BACKOFF).send()?)
+	}
+}
 
-		if let Some((name, value)) = header {
-			match sp_io::offchain::http_request_add_header(id, name, value) {
-				Ok(_) => (),
-				Err(_) => return Err("Add request header failed"),
-			};
-		}
+fn field<'a>(value: &'a JsonValue, name: &str) -> Option<&'a JsonValue> {
+	match value {
+		JsonValue::Object(object) => object.iter()
+			.find(|(key, _)| key.iter().copied().eq(name.chars()))
+			.map(|(_, value)| value),
+		_ => None,
+	}
+}
 
-		match sp_io::offchain::http_response_wait(&[id], Some(deadline))[0] {
-			HttpRequestStatus::Finished(200) => (),
-			_ => return Err("Request failed"),
-		}
+fn first_value(value: &JsonValue) -> Option<&JsonValue> {
+	match value {
+		JsonValue::Object(object) => object.first().map(|(_, value)| value),
+		_ => None,
+	}
+}
 
-		let mut result: Vec<u8> = vec![];
-		loop {
-			let mut buffer = vec![0; 1024];
-			let _read = sp_io::offchain::http_response_read_body(id, &mut buffer, Some(deadline))
-				.map_err(|_e| ());
-			result = [&result[..], &buffer[..]].concat();
-			if _read == Ok(0) {
-				break;
-			}
-		}
-		if result.len() > 0 {
-			return Ok(result);
-		} else {
-			return Err("Parse body failed");
-		}
+fn string(value: &JsonValue) -> Option<Vec<u8>> {
+	match value {
+		JsonValue::String(chars) => Some(chars.iter().map(|c| *c as u8).collect()),
+		_ => None,
+	}
+}
+
+fn number(value: &JsonValue) -> Option<f64> {
+	match value {
+		JsonValue::Number(_) => Some(value.get_number_f64()),
+		_ => None,
 	}
 }
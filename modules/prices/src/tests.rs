@@ -0,0 +1,370 @@
+#![cfg(test)]
+
+use super::*;
+
+use std::sync::Arc;
+use codec::Decode;
+use parking_lot::RwLock;
+use utilities::http::mock;
+use frame_support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight};
+use sp_core::{
+  H256,
+  offchain::{OffchainExt, TransactionPoolExt, testing::{self, TestOffchainExt, TestTransactionPoolExt}},
+  testing::KeyStore,
+  traits::{BareCryptoStore, KeystoreExt},
+};
+use sp_runtime::{
+  AccountId32, MultiSignature, MultiSigner, Perbill, DispatchError,
+  testing::{Header, TestXt},
+  traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup},
+};
+
+impl_outer_origin! {
+  pub enum Origin for Test {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+type AccountId = AccountId32;
+
+parameter_types! {
+  pub const BlockHashCount: u64 = 250;
+  pub const MaximumBlockWeight: Weight = 1024;
+  pub const MaximumBlockLength: u32 = 2 * 1024;
+  pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Test {
+  type Origin = Origin;
+  type Call = ();
+  type Index = u64;
+  type BlockNumber = u64;
+  type Hash = H256;
+  type Hashing = BlakeTwo256;
+  type AccountId = AccountId;
+  type Lookup = IdentityLookup<Self::AccountId>;
+  type Header = Header;
+  type Event = ();
+  type BlockHashCount = BlockHashCount;
+  type MaximumBlockWeight = MaximumBlockWeight;
+  type DbWeight = ();
+  type BlockExecutionWeight = ();
+  type ExtrinsicBaseWeight = ();
+  type MaximumBlockLength = MaximumBlockLength;
+  type AvailableBlockRatio = AvailableBlockRatio;
+  type Version = ();
+  type ModuleToIndex = ();
+  type AccountData = ();
+  type OnNewAccount = ();
+  type OnKilledAccount = ();
+}
+
+parameter_types! {
+  pub const MinimumPeriod: u64 = 1;
+}
+impl pallet_timestamp::Trait for Test {
+  type Moment = u64;
+  type OnTimestampSet = ();
+  type MinimumPeriod = MinimumPeriod;
+}
+
+type Extrinsic = TestXt<Call<Test>, ()>;
+
+impl frame_system::offchain::SigningTypes for Test {
+  type Public = MultiSigner;
+  type Signature = MultiSignature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test where
+  Call<Test>: From<LocalCall>,
+{
+  type OverarchingCall = Call<Test>;
+  type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Test where
+  Call<Test>: From<LocalCall>,
+{
+  fn create_transaction<C: AppCrypto<Self::Public, Self::Signature>>(
+    call: Call<Test>,
+    _public: MultiSigner,
+    _account: AccountId,
+    nonce: u64,
+  ) -> Option<(Call<Test>, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+    Some((call, (nonce, ())))
+  }
+}
+
+impl Trait for Test {
+  type AuthorityId = crypto::AuthId;
+  type Event = ();
+  type Call = Call<Test>;
+}
+
+type PricesModule = Module<Test>;
+type Timestamp = pallet_timestamp::Module<Test>;
+
+const PHRASE: &str = "news slush supreme milk chapter athlete soap sausage put clutch what kitten";
+const COINCAP_URL: &str = "https://api.coincap.io/v2/assets/bitcoin";
+const CRYPTOCOMPARE_URL: &str = "https://min-api.cryptocompare.com/data/price?fsym=BTC&tsyms=USD";
+const CMC_URL: &str = "https://pro-api.coinmarketcap.com/v1/cryptocurrency/quotes/latest?id=2275";
+const CMC_KEY_HEADER: &str = "X-CMC_PRO_API_KEY";
+const CMC_KEY: &str = "KEY";
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3/simple/price?ids=prochain&vs_currencies=usd";
+
+fn account(seed: u8) -> AccountId {
+  AccountId32::from([seed; 32])
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+  frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
+
+/// Externalities with mocked http, a transaction pool and an `ofpf` key in the
+/// keystore. Returns the account of that key.
+fn offchain_test_ext() -> (sp_io::TestExternalities, Arc<RwLock<testing::OffchainState>>, Arc<RwLock<testing::PoolState>>, AccountId) {
+  let (offchain, offchain_state) = TestOffchainExt::new();
+  let (pool, pool_state) = TestTransactionPoolExt::new();
+  let keystore = KeyStore::new();
+  let public = keystore
+    .write()
+    .sr25519_generate_new(KEY_TYPE, Some(&format!("{}/prices", PHRASE)))
+    .unwrap();
+
+  let mut t = new_test_ext();
+  t.register_extension(OffchainExt::new(offchain));
+  t.register_extension(TransactionPoolExt::new(pool));
+  t.register_extension(KeystoreExt(keystore));
+  (t, offchain_state, pool_state, MultiSigner::from(public).into_account())
+}
+
+fn source(symbol: &[u8], name: &[u8], url: &str, headers: Vec<(Vec<u8>, Vec<u8>)>) -> PriceSource {
+  PriceSource {
+    symbol: symbol.to_vec(),
+    source: name.to_vec(),
+    endpoint: http::Endpoint { url: url.as_bytes().to_vec(), headers },
+  }
+}
+
+fn set_sources(sources: Vec<PriceSource>) {
+  StorageValueRef::persistent(SOURCES_KEY).set(&sources);
+}
+
+fn btc_sources() -> Vec<PriceSource> {
+  vec![
+    source(b"BTC", b"coincap", COINCAP_URL, vec![]),
+    source(b"BTC", b"cryptocompare", CRYPTOCOMPARE_URL, vec![]),
+  ]
+}
+
+fn prm_sources() -> Vec<PriceSource> {
+  vec![
+    source(b"PRM", b"coinmarketcap", CMC_URL, vec![(CMC_KEY_HEADER.as_bytes().to_vec(), CMC_KEY.as_bytes().to_vec())]),
+    source(b"PRM", b"coingecko", COINGECKO_URL, vec![]),
+  ]
+}
+
+fn coincap_response(symbol: &str, price: &str) -> Vec<u8> {
+  format!(
+    r#"{{"data":{{"id":"bitcoin","rank":"1","symbol":"{}","name":"Bitcoin","priceUsd":"{}"}},"timestamp":1590000000000}}"#,
+    symbol, price,
+  ).into_bytes()
+}
+
+fn cryptocompare_response(price: &str) -> Vec<u8> {
+  format!(r#"{{"USD":{}}}"#, price).into_bytes()
+}
+
+fn cmc_response(price: &str) -> Vec<u8> {
+  format!(
+    r#"{{"status":{{"error_code":0}},"data":{{"2275":{{"id":2275,"name":"ProChain","symbol":"PRM","quote":{{"USD":{{"price":{},"volume_24h":1000}}}}}}}}}}"#,
+    price,
+  ).into_bytes()
+}
+
+fn coingecko_response(price: &str) -> Vec<u8> {
+  format!(r#"{{"prochain":{{"usd":{}}}}}"#, price).into_bytes()
+}
+
+fn expect_cmc(state: &mut testing::OffchainState, id: u16, response: Vec<u8>) {
+  let request = http::Request::get(CMC_URL).header(CMC_KEY_HEADER, CMC_KEY);
+  mock::expect_request(state, id, &request, &response);
+}
+
+fn price(value: u128) -> Price {
+  // 8 decimals
+  FixedU128::from_rational(value, 100_000_000)
+}
+
+/// The single transaction in the pool, with its nonce.
+fn submitted(pool_state: &Arc<RwLock<testing::PoolState>>) -> (u64, Call<Test>) {
+  let tx = pool_state.write().transactions.pop().unwrap();
+  assert!(pool_state.read().transactions.is_empty());
+  let tx = Extrinsic::decode(&mut &*tx).unwrap();
+  (tx.signature.unwrap().0, tx.call)
+}
+
+#[test]
+fn feed_value_should_need_authority() {
+  new_test_ext().execute_with(|| {
+    assert_noop!(
+      PricesModule::feed_value(Origin::signed(account(1)), CURRENCY_PRM, CURRENCY_USD, price(1_000_000)),
+      Error::<Test>::NoPermission
+    );
+    assert_noop!(PricesModule::set_authority(Origin::signed(account(1)), account(1)), DispatchError::BadOrigin);
+    assert_ok!(PricesModule::set_authority(Origin::ROOT, account(1)));
+    assert_noop!(
+      PricesModule::feed_value(Origin::signed(account(1)), CURRENCY_PRM, CURRENCY_BTC, price(1_000_000)),
+      Error::<Test>::UnsupportedQuote
+    );
+    assert_noop!(
+      PricesModule::feed_value(Origin::signed(account(1)), CURRENCY_PRM, CURRENCY_USD, price(0)),
+      Error::<Test>::ZeroPrice
+    );
+
+    Timestamp::set_timestamp(42);
+    assert_ok!(PricesModule::feed_value(Origin::signed(account(1)), CURRENCY_PRM, CURRENCY_USD, price(1_000_000)));
+    assert_eq!(PricesModule::values(CURRENCY_PRM), Some(PriceValue {
+      currency_id: CURRENCY_PRM,
+      quote_currency_id: CURRENCY_USD,
+      price: price(1_000_000),
+      account: account(1),
+      timestamp: 42,
+    }));
+  });
+}
+
+#[test]
+fn get_price_should_cross_usd_prices() {
+  new_test_ext().execute_with(|| {
+    assert_ok!(PricesModule::set_authority(Origin::ROOT, account(1)));
+    assert_eq!(PricesModule::get_price(CURRENCY_USD, CURRENCY_PRM), None);
+
+    Timestamp::set_timestamp(10);
+    // 1 PRM = 0.02 USD
+    assert_ok!(PricesModule::feed_value(Origin::signed(account(1)), CURRENCY_PRM, CURRENCY_USD, price(2_000_000)));
+    Timestamp::set_timestamp(20);
+    // 1 BTC = 10000 USD
+    assert_ok!(PricesModule::feed_value(Origin::signed(account(1)), CURRENCY_BTC, CURRENCY_USD, price(1_000_000_000_000)));
+
+    assert_eq!(PricesModule::get_price(CURRENCY_PRM, CURRENCY_USD), Some(price(2_000_000)));
+    assert_eq!(PricesModule::get_price_with_timestamp(CURRENCY_USD, CURRENCY_PRM), Some((FixedU128::from_natural(50), 10)));
+    assert_eq!(PricesModule::get_price_with_timestamp(CURRENCY_BTC, CURRENCY_PRM), Some((FixedU128::from_natural(500_000), 10)));
+    assert_eq!(PricesModule::get_price(CURRENCY_USD, CURRENCY_USD), None);
+  });
+}
+
+#[test]
+fn offchain_worker_should_feed_btc_from_coincap_and_cryptocompare() {
+  let (mut t, offchain_state, pool_state, authority) = offchain_test_ext();
+  {
+    let mut state = offchain_state.write();
+    mock::expect_get(&mut state, 0, COINCAP_URL, &coincap_response("BTC", "9000.123456789"));
+    mock::expect_get(&mut state, 1, CRYPTOCOMPARE_URL, &cryptocompare_response("9001.5"));
+  }
+
+  t.execute_with(|| {
+    assert_ok!(PricesModule::set_authority(Origin::ROOT, authority));
+    set_sources([btc_sources(), prm_sources()].concat());
+
+    assert_ok!(PricesModule::offchain_prices(0));
+
+    // 9000.12345679 and 9001.5 averaged
+    let (nonce, call) = submitted(&pool_state);
+    assert_eq!(nonce, 0);
+    assert_eq!(call, Call::feed_value(CURRENCY_BTC, CURRENCY_USD, FixedU128::from_rational(1_800_162_345_679u128, 200_000_000)));
+  });
+}
+
+#[test]
+fn offchain_worker_should_feed_prm_from_cmc_and_coingecko() {
+  let (mut t, offchain_state, pool_state, authority) = offchain_test_ext();
+  {
+    let mut state = offchain_state.write();
+    expect_cmc(&mut state, 0, cmc_response("0.0125"));
+    mock::expect_get(&mut state, 1, COINGECKO_URL, &coingecko_response("0.0135"));
+  }
+
+  t.execute_with(|| {
+    assert_ok!(PricesModule::set_authority(Origin::ROOT, authority.clone()));
+    set_sources([btc_sources(), prm_sources()].concat());
+
+    assert_ok!(PricesModule::offchain_prices(1));
+
+    let (_, call) = submitted(&pool_state);
+    assert_eq!(call, Call::feed_value(CURRENCY_PRM, CURRENCY_USD, price(1_300_000)));
+
+    // the submitted call is accepted from the authority
+    assert_ok!(PricesModule::feed_value(Origin::signed(authority), CURRENCY_PRM, CURRENCY_USD, price(1_300_000)));
+    assert_eq!(PricesModule::get_price(CURRENCY_PRM, CURRENCY_USD), Some(price(1_300_000)));
+  });
+}
+
+#[test]
+fn offchain_worker_should_skip_bad_responses() {
+  let (mut t, offchain_state, pool_state, authority) = offchain_test_ext();
+  {
+    let mut state = offchain_state.write();
+    mock::expect_get(&mut state, 0, COINCAP_URL, &coincap_response("ETH", "200.5"));
+    mock::expect_get(&mut state, 1, CRYPTOCOMPARE_URL, &cryptocompare_response("9001.5"));
+    expect_cmc(&mut state, 2, br#"{"status":{"error_code":1002},"data":{}}"#.to_vec());
+    mock::expect_get(&mut state, 3, COINGECKO_URL, b"{}");
+  }
+
+  t.execute_with(|| {
+    assert_ok!(PricesModule::set_authority(Origin::ROOT, authority));
+    set_sources([btc_sources(), prm_sources()].concat());
+
+    // coincap answered for another symbol
+    assert_ok!(PricesModule::offchain_prices(0));
+    let (_, call) = submitted(&pool_state);
+    assert_eq!(call, Call::feed_value(CURRENCY_BTC, CURRENCY_USD, price(900_150_000_000)));
+
+    // no source answered with a price
+    assert_eq!(PricesModule::offchain_prices(1), Err("error no price fetched"));
+    assert!(pool_state.read().transactions.is_empty());
+  });
+}
+
+#[test]
+fn offchain_worker_should_need_local_key() {
+  let (offchain, _) = TestOffchainExt::new();
+  let (pool, pool_state) = TestTransactionPoolExt::new();
+  let mut t = new_test_ext();
+  t.register_extension(OffchainExt::new(offchain));
+  t.register_extension(TransactionPoolExt::new(pool));
+  t.register_extension(KeystoreExt(KeyStore::new()));
+
+  t.execute_with(|| {
+    assert_ok!(PricesModule::set_authority(Origin::ROOT, account(1)));
+    set_sources(vec![source(b"PRM", b"coingecko", COINGECKO_URL, vec![])]);
+
+    // nothing fetched nor submitted without a key
+    assert_ok!(PricesModule::offchain_prices(0));
+    assert!(pool_state.read().transactions.is_empty());
+  });
+}
+
+#[test]
+fn offchain_worker_should_skip_keys_of_other_accounts() {
+  let (mut t, _, pool_state, _) = offchain_test_ext();
+
+  t.execute_with(|| {
+    set_sources(vec![source(b"PRM", b"coingecko", COINGECKO_URL, vec![])]);
+
+    // no authority set yet
+    assert_ok!(PricesModule::offchain_prices(0));
+    assert!(pool_state.read().transactions.is_empty());
+
+    // the local key is not the authority
+    assert_ok!(PricesModule::set_authority(Origin::ROOT, account(1)));
+    assert_ok!(PricesModule::offchain_prices(0));
+    assert!(pool_state.read().transactions.is_empty());
+  });
+}
+
+#[test]
+fn average_prices_should_work() {
+  assert_eq!(PricesModule::average_prices(vec![]), None);
+  assert_eq!(PricesModule::average_prices(vec![price(100), price(200), price(600)]), Some(price(300)));
+}
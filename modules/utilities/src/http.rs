@@ -0,0 +1,354 @@
+//! HTTP client for offchain workers.
+//!
+//! Requests are built with [`Request`] and sent through a [`Transport`], the
+//! offchain externalities by default. Each attempt runs against its own
+//! deadline and transient failures are retried with exponential backoff.
+
+use codec::{Decode, Encode};
+use rstd::prelude::*;
+use sp_core::offchain::{Duration, HttpError, HttpRequestStatus, Timestamp};
+use sp_runtime::offchain::storage::StorageValueRef;
+
+/// Deadline of a single attempt in milliseconds.
+pub const DEFAULT_TIMEOUT: u64 = 10_000;
+/// Largest response body accepted in bytes.
+pub const DEFAULT_MAX_BODY: usize = 1 << 20;
+/// Wait before the first retry in milliseconds, doubled on every retry.
+pub const DEFAULT_BACKOFF: u64 = 500;
+
+const READ_CHUNK: usize = 1024;
+
+/// Reasons an HTTP request failed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error {
+	/// The request could not be started, e.g. an invalid url.
+	StartFailed,
+	/// A header could not be added.
+	HeaderFailed,
+	/// The deadline passed before the response was complete.
+	DeadlineReached,
+	/// The connection failed or the request id is no longer valid.
+	IoError,
+	/// The server answered with a status other than 200.
+	Status(u16),
+	/// The response body exceeds the size limit.
+	BodyTooLarge,
+	/// The response body is empty.
+	EmptyBody,
+}
+
+impl Error {
+	/// Whether another attempt may succeed.
+	pub fn is_transient(&self) -> bool {
+		match self {
+			Error::DeadlineReached | Error::IoError | Error::EmptyBody => true,
+			Error::Status(code) => *code == 429 || *code >= 500,
+			Error::StartFailed | Error::HeaderFailed | Error::BodyTooLarge => false,
+		}
+	}
+}
+
+impl From<Error> for &'static str {
+	fn from(e: Error) -> &'static str {
+		match e {
+			Error::StartFailed => "http request start failed",
+			Error::HeaderFailed => "http request header failed",
+			Error::DeadlineReached => "http request deadline reached",
+			Error::IoError => "http request io error",
+			Error::Status(_) => "http request non-200 status returned",
+			Error::BodyTooLarge => "http response body too large",
+			Error::EmptyBody => "http response body empty",
+		}
+	}
+}
+
+impl From<HttpError> for Error {
+	fn from(e: HttpError) -> Self {
+		match e {
+			HttpError::DeadlineReached => Error::DeadlineReached,
+			HttpError::IoError | HttpError::Invalid => Error::IoError,
+		}
+	}
+}
+
+/// A url and the headers sent to it. Endpoints holding API keys are kept in
+/// offchain local storage and never stored on chain.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Endpoint {
+	pub url: Vec<u8>,
+	pub headers: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Endpoint {
+	/// Endpoint stored under `key` in persistent offchain local storage.
+	pub fn load(key: &[u8]) -> Option<Self> {
+		StorageValueRef::persistent(key).get::<Self>().flatten()
+	}
+
+	pub fn url(&self) -> Result<&str, Error> {
+		core::str::from_utf8(&self.url).map_err(|_| Error::StartFailed)
+	}
+
+	/// Add the endpoint headers to `request`.
+	pub fn with_headers<'a>(&'a self, mut request: Request<'a>) -> Result<Request<'a>, Error> {
+		for (name, value) in self.headers.iter() {
+			let name = core::str::from_utf8(name).map_err(|_| Error::HeaderFailed)?;
+			let value = core::str::from_utf8(value).map_err(|_| Error::HeaderFailed)?;
+			request = request.header(name, value);
+		}
+		Ok(request)
+	}
+}
+
+/// Sends single request attempts.
+pub trait Transport {
+	/// Current time.
+	fn timestamp(&self) -> Timestamp;
+
+	/// Block until `deadline` before the next attempt.
+	fn sleep_until(&self, deadline: Timestamp);
+
+	/// Send `request` once and return the response body.
+	fn fetch(&self, request: &Request, deadline: Timestamp) -> Result<Vec<u8>, Error>;
+}
+
+/// Transport over the offchain worker externalities.
+pub struct Offchain;
+
+impl Transport for Offchain {
+	fn timestamp(&self) -> Timestamp {
+		sp_io::offchain::timestamp()
+	}
+
+	fn sleep_until(&self, deadline: Timestamp) {
+		sp_io::offchain::sleep_until(deadline)
+	}
+
+	fn fetch(&self, request: &Request, deadline: Timestamp) -> Result<Vec<u8>, Error> {
+		let id = sp_io::offchain::http_request_start(request.method, request.url, &[])
+			.map_err(|_| Error::StartFailed)?;
+		for (name, value) in request.headers.iter() {
+			sp_io::offchain::http_request_add_header(id, name, value).map_err(|_| Error::HeaderFailed)?;
+		}
+		if !request.body.is_empty() {
+			sp_io::offchain::http_request_write_body(id, request.body, Some(deadline))?;
+		}
+		// an empty chunk finishes the request body
+		sp_io::offchain::http_request_write_body(id, &[], Some(deadline))?;
+
+		match sp_io::offchain::http_response_wait(&[id], Some(deadline))[0] {
+			HttpRequestStatus::Finished(200) => (),
+			HttpRequestStatus::Finished(code) => return Err(Error::Status(code)),
+			HttpRequestStatus::DeadlineReached => return Err(Error::DeadlineReached),
+			HttpRequestStatus::IoError | HttpRequestStatus::Invalid => return Err(Error::IoError),
+		}
+
+		let mut body = Vec::new();
+		let mut buffer = [0u8; READ_CHUNK];
+		loop {
+			let read = sp_io::offchain::http_response_read_body(id, &mut buffer, Some(deadline))? as usize;
+			if read == 0 {
+				break;
+			}
+			if body.len() + read > request.max_body {
+				return Err(Error::BodyTooLarge);
+			}
+			body.extend_from_slice(&buffer[..read]);
+		}
+		Ok(body)
+	}
+}
+
+/// An HTTP request with its deadline, retry and size settings.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Request<'a> {
+	pub method: &'a str,
+	pub url: &'a str,
+	pub headers: Vec<(&'a str, &'a str)>,
+	pub body: &'a [u8],
+	/// Deadline of each attempt in milliseconds.
+	pub timeout: u64,
+	/// Attempts after the first one failed with a transient error.
+	pub retries: u32,
+	/// Wait before the first retry in milliseconds.
+	pub backoff: u64,
+	/// Largest response body accepted in bytes.
+	pub max_body: usize,
+}
+
+impl<'a> Request<'a> {
+	/// A request without retries and the default deadline and size limit.
+	pub fn new(method: &'a str, url: &'a str) -> Self {
+		Request {
+			method,
+			url,
+			headers: Vec::new(),
+			body: &[],
+			timeout: DEFAULT_TIMEOUT,
+			retries: 0,
+			backoff: DEFAULT_BACKOFF,
+			max_body: DEFAULT_MAX_BODY,
+		}
+	}
+
+	pub fn get(url: &'a str) -> Self {
+		Self::new("GET", url)
+	}
+
+	pub fn post(url: &'a str, body: &'a [u8]) -> Self {
+		Request { body, ..Self::new("POST", url) }
+	}
+
+	pub fn header(mut self, name: &'a str, value: &'a str) -> Self {
+		self.headers.push((name, value));
+		self
+	}
+
+	pub fn timeout(mut self, millis: u64) -> Self {
+		self.timeout = millis;
+		self
+	}
+
+	pub fn retries(mut self, retries: u32, backoff: u64) -> Self {
+		self.retries = retries;
+		self.backoff = backoff;
+		self
+	}
+
+	pub fn max_body(mut self, bytes: usize) -> Self {
+		self.max_body = bytes;
+		self
+	}
+
+	/// Send through the offchain externalities.
+	pub fn send(&self) -> Result<Vec<u8>, Error> {
+		self.send_with(&Offchain)
+	}
+
+	/// Send through `transport`, retrying transient failures.
+	pub fn send_with<T: Transport>(&self, transport: &T) -> Result<Vec<u8>, Error> {
+		let mut backoff = self.backoff;
+		let mut attempt = 0;
+		loop {
+			let deadline = transport.timestamp().add(Duration::from_millis(self.timeout));
+			let result = transport.fetch(self, deadline).and_then(|body| {
+				if body.is_empty() {
+					Err(Error::EmptyBody)
+				} else {
+					Ok(body)
+				}
+			});
+			match result {
+				Err(e) if e.is_transient() && attempt < self.retries => {
+					attempt += 1;
+					transport.sleep_until(transport.timestamp().add(Duration::from_millis(backoff)));
+					backoff = backoff.saturating_mul(2);
+				},
+				result => return result,
+			}
+		}
+	}
+}
+
+/// Test doubles for code sending requests, enabled by the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod mock {
+	use super::*;
+	use std::{cell::{Cell, RefCell}, collections::VecDeque};
+	use sp_core::offchain::testing::{OffchainState, PendingRequest};
+
+	/// Transport answering from a queue of expected requests on a manual clock.
+	#[derive(Default)]
+	pub struct MockTransport {
+		now: Cell<u64>,
+		expected: RefCell<VecDeque<(String, String, Result<Vec<u8>, Error>)>>,
+		headers: RefCell<Vec<Vec<(String, String)>>>,
+		deadlines: RefCell<Vec<u64>>,
+		sleeps: RefCell<Vec<u64>>,
+	}
+
+	impl MockTransport {
+		/// Expect `method` on `url` next and answer with `response`.
+		pub fn expect(&self, method: &str, url: &str, response: Result<Vec<u8>, Error>) {
+			self.expected.borrow_mut().push_back((method.into(), url.into(), response));
+		}
+
+		/// Headers of each request sent so far.
+		pub fn sent_headers(&self) -> Vec<Vec<(String, String)>> {
+			self.headers.borrow().clone()
+		}
+
+		/// Deadline of each attempt in milliseconds after the clock start.
+		pub fn deadlines(&self) -> Vec<u64> {
+			self.deadlines.borrow().clone()
+		}
+
+		/// Length of each backoff in milliseconds.
+		pub fn sleeps(&self) -> Vec<u64> {
+			self.sleeps.borrow().clone()
+		}
+
+		/// Panics if an expected request was not sent.
+		pub fn assert_done(&self) {
+			assert!(self.expected.borrow().is_empty(), "expected requests not sent: {:?}", self.expected.borrow());
+		}
+	}
+
+	impl Transport for MockTransport {
+		fn timestamp(&self) -> Timestamp {
+			Timestamp::from_unix_millis(self.now.get())
+		}
+
+		fn sleep_until(&self, deadline: Timestamp) {
+			self.sleeps.borrow_mut().push(deadline.unix_millis() - self.now.get());
+			self.now.set(deadline.unix_millis());
+		}
+
+		fn fetch(&self, request: &Request, deadline: Timestamp) -> Result<Vec<u8>, Error> {
+			let (method, url, response) = self.expected.borrow_mut().pop_front()
+				.unwrap_or_else(|| panic!("unexpected request: {} {}", request.method, request.url));
+			assert_eq!((request.method, request.url), (method.as_str(), url.as_str()));
+
+			self.headers.borrow_mut().push(owned_headers(request));
+			self.deadlines.borrow_mut().push(deadline.unix_millis());
+
+			let body = response?;
+			if body.len() > request.max_body {
+				return Err(Error::BodyTooLarge);
+			}
+			Ok(body)
+		}
+	}
+
+	fn owned_headers(request: &Request) -> Vec<(String, String)> {
+		request.headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+	}
+
+	/// Expect a request sent through [`Offchain`] on the `sp_core` test state.
+	pub fn expect_request(
+		state: &mut OffchainState,
+		id: u16,
+		request: &Request,
+		response: &[u8],
+	) {
+		state.expect_request(id, PendingRequest {
+			method: request.method.into(),
+			uri: request.url.into(),
+			headers: owned_headers(request),
+			body: request.body.to_vec(),
+			response: Some(response.to_vec()),
+			sent: true,
+			..Default::default()
+		});
+	}
+
+	/// Expect a GET of `url` answered with `response`.
+	pub fn expect_get(state: &mut OffchainState, id: u16, url: &str, response: &[u8]) {
+		expect_request(state, id, &Request::get(url), response)
+	}
+
+	/// Expect a POST of `body` to `url` answered with `response`.
+	pub fn expect_post(state: &mut OffchainState, id: u16, url: &str, body: &[u8], response: &[u8]) {
+		expect_request(state, id, &Request::post(url, body), response)
+	}
+}
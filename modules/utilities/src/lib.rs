@@ -1,4 +1,8 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+mod tests;
 pub mod fixed128;
+pub mod http;
+pub mod linked_list;
 pub use fixed128::FixedU128;
+pub use linked_list::{LinkedItem, LinkedList};
@@ -0,0 +1,143 @@
+//! A doubly linked list kept in a storage map, ordered by insertion.
+//!
+//! Every value is stored under `Some(value)` together with its neighbours and
+//! the head item under `None` points at the first and last values, so append,
+//! remove and membership checks touch a constant number of entries.
+
+use codec::{Decode, Encode};
+use frame_support::{Parameter, StorageMap, StorageValue};
+use rstd::{marker::PhantomData, prelude::*};
+use sp_runtime::traits::Member;
+
+/// Neighbours of a list value. For the head item `prev` is the last value and
+/// `next` the first one.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct LinkedItem<Value> {
+	pub prev: Option<Value>,
+	pub next: Option<Value>,
+}
+
+/// Storage backed list of unique values.
+///
+/// `Storage` holds the links, keyed by `Some(value)` plus the head at `None`,
+/// and `SizeStorage` the number of values in the list.
+pub struct LinkedList<Storage, Value, SizeStorage>(PhantomData<(Storage, Value, SizeStorage)>);
+
+impl<Storage, Value, SizeStorage> LinkedList<Storage, Value, SizeStorage> where
+	Value: Parameter + Member + Copy,
+	Storage: StorageMap<Option<Value>, LinkedItem<Value>, Query = Option<LinkedItem<Value>>>,
+	SizeStorage: StorageValue<u64, Query = u64>,
+{
+	fn read(value: Option<Value>) -> LinkedItem<Value> {
+		Storage::get(value).unwrap_or_else(|| LinkedItem { prev: None, next: None })
+	}
+
+	fn write(value: Option<Value>, item: LinkedItem<Value>) {
+		Storage::insert(value, item);
+	}
+
+	pub fn size() -> u64 {
+		SizeStorage::get()
+	}
+
+	pub fn contains(value: &Value) -> bool {
+		Storage::contains_key(Some(*value))
+	}
+
+	pub fn first() -> Option<Value> {
+		Self::read(None).next
+	}
+
+	pub fn last() -> Option<Value> {
+		Self::read(None).prev
+	}
+
+	/// Value following `value`, `None` at the end of the list or if `value` is not in it.
+	pub fn next(value: &Value) -> Option<Value> {
+		Storage::get(Some(*value)).and_then(|item| item.next)
+	}
+
+	/// Add `value` at the end of the list. Returns `false` if it is already in it.
+	pub fn append(value: &Value) -> bool {
+		if Self::contains(value) {
+			return false;
+		}
+		let head = Self::read(None);
+		let last = head.prev;
+
+		let mut prev = Self::read(last);
+		prev.next = Some(*value);
+		Self::write(last, prev);
+
+		// re-read the head, it is the item just written when the list was empty
+		let mut head = Self::read(None);
+		head.prev = Some(*value);
+		Self::write(None, head);
+
+		Self::write(Some(*value), LinkedItem { prev: last, next: None });
+		SizeStorage::put(Self::size().saturating_add(1));
+		true
+	}
+
+	/// Unlink `value`. Returns `false` if it is not in the list.
+	pub fn remove(value: &Value) -> bool {
+		let item = match Storage::take(Some(*value)) {
+			Some(item) => item,
+			None => return false,
+		};
+
+		let mut prev = Self::read(item.prev);
+		prev.next = item.next;
+		Self::write(item.prev, prev);
+
+		let mut next = Self::read(item.next);
+		next.prev = item.prev;
+		if item.next.is_none() && next.next.is_none() && next.prev.is_none() {
+			// the list is empty again
+			Storage::remove(None::<Value>);
+		} else {
+			Self::write(item.next, next);
+		}
+
+		SizeStorage::put(Self::size().saturating_sub(1));
+		true
+	}
+
+	/// Values in insertion order.
+	pub fn iter() -> Iter<Storage, Value, SizeStorage> {
+		Iter { next: Self::first(), _marker: PhantomData }
+	}
+
+	/// Up to `limit` values following `after`, or from the start of the list
+	/// when `after` is `None`.
+	pub fn page(after: Option<Value>, limit: usize) -> Vec<Value> {
+		let start = match after {
+			Some(value) => Self::next(&value),
+			None => Self::first(),
+		};
+		Iter::<Storage, Value, SizeStorage> { next: start, _marker: PhantomData }
+			.take(limit)
+			.collect()
+	}
+}
+
+/// Iterator over a [`LinkedList`], reading one link from storage per value.
+pub struct Iter<Storage, Value, SizeStorage> {
+	next: Option<Value>,
+	_marker: PhantomData<(Storage, SizeStorage)>,
+}
+
+impl<Storage, Value, SizeStorage> Iterator for Iter<Storage, Value, SizeStorage> where
+	Value: Parameter + Member + Copy,
+	Storage: StorageMap<Option<Value>, LinkedItem<Value>, Query = Option<LinkedItem<Value>>>,
+	SizeStorage: StorageValue<u64, Query = u64>,
+{
+	type Item = Value;
+
+	fn next(&mut self) -> Option<Value> {
+		let current = self.next?;
+		self.next = LinkedList::<Storage, Value, SizeStorage>::next(&current);
+		Some(current)
+	}
+}
@@ -0,0 +1,240 @@
+#![cfg(test)]
+
+use super::*;
+
+use frame_support::{decl_module, decl_storage, impl_outer_origin, parameter_types, StorageMap, weights::Weight};
+use sp_core::H256;
+use sp_runtime::{
+  Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup},
+};
+
+impl_outer_origin! {
+  pub enum Origin for Test {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+  pub const BlockHashCount: u64 = 250;
+  pub const MaximumBlockWeight: Weight = 1024;
+  pub const MaximumBlockLength: u32 = 2 * 1024;
+  pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Test {
+  type Origin = Origin;
+  type Call = ();
+  type Index = u64;
+  type BlockNumber = u64;
+  type Hash = H256;
+  type Hashing = BlakeTwo256;
+  type AccountId = u64;
+  type Lookup = IdentityLookup<Self::AccountId>;
+  type Header = Header;
+  type Event = ();
+  type BlockHashCount = BlockHashCount;
+  type MaximumBlockWeight = MaximumBlockWeight;
+  type DbWeight = ();
+  type BlockExecutionWeight = ();
+  type ExtrinsicBaseWeight = ();
+  type MaximumBlockLength = MaximumBlockLength;
+  type AvailableBlockRatio = AvailableBlockRatio;
+  type Version = ();
+  type ModuleToIndex = ();
+  type AccountData = ();
+  type OnNewAccount = ();
+  type OnKilledAccount = ();
+}
+
+pub trait Trait: frame_system::Trait {}
+impl Trait for Test {}
+
+decl_module! {
+  pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+}
+
+decl_storage! {
+  trait Store for Module<T: Trait> as UtilitiesTest {
+    pub Links: map hasher(twox_64_concat) Option<u32> => Option<LinkedItem<u32>>;
+    pub Size: u64;
+  }
+}
+
+type List = LinkedList<Links, u32, Size>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+  frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
+
+fn values() -> Vec<u32> {
+  List::iter().collect()
+}
+
+/// Check the list against the expected values, walking it both ways.
+fn assert_list(expected: &[u32]) {
+  assert_eq!(values(), expected.to_vec());
+  assert_eq!(List::size(), expected.len() as u64);
+  assert_eq!(List::first(), expected.first().copied());
+  assert_eq!(List::last(), expected.last().copied());
+
+  let mut backwards = Vec::new();
+  let mut cursor = List::last();
+  while let Some(value) = cursor {
+    backwards.push(value);
+    cursor = Links::get(Some(value)).unwrap().prev;
+  }
+  backwards.reverse();
+  assert_eq!(backwards, expected.to_vec());
+}
+
+#[test]
+fn append_and_remove_should_keep_order() {
+  new_test_ext().execute_with(|| {
+    assert_list(&[]);
+    assert!(List::append(&1));
+    assert!(List::append(&2));
+    assert!(List::append(&3));
+    assert!(!List::append(&2));
+    assert_list(&[1, 2, 3]);
+
+    // middle, first and last
+    assert!(List::remove(&2));
+    assert_list(&[1, 3]);
+    assert!(List::remove(&1));
+    assert_list(&[3]);
+    assert!(!List::remove(&1));
+    assert!(List::remove(&3));
+    assert_list(&[]);
+    assert!(!Links::contains_key(None::<u32>));
+
+    assert!(List::append(&4));
+    assert_list(&[4]);
+  });
+}
+
+#[test]
+fn page_should_continue_after_cursor() {
+  new_test_ext().execute_with(|| {
+    for value in 10..20 {
+      List::append(&value);
+    }
+    assert_eq!(List::page(None, 3), vec![10, 11, 12]);
+    assert_eq!(List::page(Some(12), 3), vec![13, 14, 15]);
+    assert_eq!(List::page(Some(18), 3), vec![19]);
+    assert_eq!(List::page(Some(19), 3), Vec::<u32>::new());
+    assert_eq!(List::page(Some(42), 3), Vec::<u32>::new());
+  });
+}
+
+/// xorshift, enough to drive random operations reproducibly without extra dependencies
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    self.0
+  }
+}
+
+#[test]
+fn random_operations_should_match_vec_model() {
+  for seed in 1..=20u64 {
+    new_test_ext().execute_with(|| {
+      let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+      let mut model: Vec<u32> = Vec::new();
+
+      for _ in 0..200 {
+        let value = (rng.next() % 32) as u32;
+        if rng.next() % 3 == 0 {
+          let expected = model.iter().position(|v| *v == value).map(|i| model.remove(i)).is_some();
+          assert_eq!(List::remove(&value), expected);
+        } else {
+          let expected = !model.contains(&value);
+          if expected {
+            model.push(value);
+          }
+          assert_eq!(List::append(&value), expected);
+        }
+        assert_eq!(List::contains(&value), model.contains(&value));
+      }
+
+      assert_list(&model);
+      let mut paged = Vec::new();
+      let mut after = None;
+      loop {
+        let page = List::page(after, 7);
+        if page.is_empty() {
+          break;
+        }
+        after = page.last().copied();
+        paged.extend(page);
+      }
+      assert_eq!(paged, model);
+    });
+  }
+}
+
+mod http_client {
+  use crate::http::{mock::{self, MockTransport}, Error, Request};
+  use sp_core::offchain::{testing, OffchainExt};
+
+  const URL: &str = "https://example.com/price";
+
+  #[test]
+  fn retries_should_back_off_on_transient_errors() {
+    let transport = MockTransport::default();
+    transport.expect("GET", URL, Err(Error::DeadlineReached));
+    transport.expect("GET", URL, Err(Error::Status(503)));
+    transport.expect("GET", URL, Ok(b"42".to_vec()));
+
+    let request = Request::get(URL).timeout(2_000).retries(2, 100);
+    assert_eq!(request.send_with(&transport), Ok(b"42".to_vec()));
+    assert_eq!(transport.sleeps(), vec![100, 200]);
+    assert_eq!(transport.deadlines(), vec![2_000, 2_100, 2_300]);
+    transport.assert_done();
+  }
+
+  #[test]
+  fn retries_should_stop_after_limit() {
+    let transport = MockTransport::default();
+    transport.expect("GET", URL, Err(Error::IoError));
+    transport.expect("GET", URL, Ok(vec![]));
+
+    assert_eq!(Request::get(URL).retries(1, 100).send_with(&transport), Err(Error::EmptyBody));
+    transport.assert_done();
+  }
+
+  #[test]
+  fn permanent_errors_should_not_retry() {
+    let transport = MockTransport::default();
+    transport.expect("GET", URL, Err(Error::Status(404)));
+    assert_eq!(Request::get(URL).retries(3, 100).send_with(&transport), Err(Error::Status(404)));
+
+    transport.expect("POST", URL, Ok(vec![1; 11]));
+    let request = Request::post(URL, b"{}").header("Content-Type", "application/json").max_body(10).retries(3, 100);
+    assert_eq!(request.send_with(&transport), Err(Error::BodyTooLarge));
+    assert_eq!(transport.sent_headers()[1], vec![("Content-Type".to_string(), "application/json".to_string())]);
+    assert!(transport.sleeps().is_empty());
+    transport.assert_done();
+  }
+
+  #[test]
+  fn offchain_transport_should_read_whole_body() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = sp_io::TestExternalities::default();
+    t.register_extension(OffchainExt::new(offchain));
+
+    // longer than one read chunk and not a multiple of it
+    let response = vec![b'7'; 2_500];
+    let request = Request::post(URL, b"{}").header("X-Key", "secret");
+    mock::expect_request(&mut state.write(), 0, &request, &response);
+    mock::expect_get(&mut state.write(), 1, URL, &response);
+
+    t.execute_with(|| {
+      assert_eq!(request.send(), Ok(response.clone()));
+      assert_eq!(Request::get(URL).max_body(2_048).send(), Err(Error::BodyTooLarge));
+    });
+  }
+}
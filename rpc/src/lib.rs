@@ -104,6 +104,9 @@ pub fn create_full<C, P, M, SC>(
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
 	C::Api: BabeApi<Block>,
+	C::Api: ads_rpc::AdsRuntimeApi<Block, Hash, Balance>,
+	C::Api: did_rpc::DidRuntimeApi<Block, Hash, Balance>,
+	C::Api: oracle_rpc::OracleSwapRuntimeApi<Block, AccountId, Hash, Balance, BlockNumber>,
 	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
 	P: TransactionPool + 'static,
 	M: jsonrpc_core::Metadata + Default,
@@ -112,6 +115,9 @@ pub fn create_full<C, P, M, SC>(
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use ads_rpc::{Ads, AdsApi};
+	use did_rpc::{Did, DidApi};
+	use oracle_rpc::{OracleSwap, OracleSwapApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
@@ -144,6 +150,15 @@ pub fn create_full<C, P, M, SC>(
 	io.extend_with(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
+	io.extend_with(
+		AdsApi::to_delegate(Ads::new(client.clone()))
+	);
+	io.extend_with(
+		DidApi::to_delegate(Did::new(client.clone()))
+	);
+	io.extend_with(
+		OracleSwapApi::to_delegate(OracleSwap::new(client.clone()))
+	);
 	io.extend_with(
 		sc_consensus_babe_rpc::BabeApi::to_delegate(
 			BabeRpcHandler::new(
@@ -17,12 +17,12 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use node_primitives::Balance;
+use node_primitives::{Balance, Moment};
 use sp_runtime::traits::{Convert, Saturating};
 use sp_runtime::{FixedPointNumber, Perquintill};
 use frame_support::traits::{OnUnbalanced, Currency, Get};
 use pallet_transaction_payment::Multiplier;
-use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance};
+use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance, Prices, Timestamp};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -98,6 +98,17 @@ impl<T: Get<Perquintill>> Convert<Multiplier, Multiplier> for TargetedFeeAdjustm
 	}
 }
 
+/// Ads fee rates from the prices pallet: native PRM paid for one unit of the fee currency.
+pub struct AdsPrices;
+impl ads::PriceProvider<Moment> for AdsPrices {
+	fn native_price(currency: &ads::FeeCurrency) -> Option<(ads::Price, Moment)> {
+		match currency {
+			ads::FeeCurrency::PRM => Some((ads::Price::from_natural(1), Timestamp::get())),
+			ads::FeeCurrency::USD => Prices::get_price_with_timestamp(prices::CURRENCY_USD, prices::CURRENCY_PRM),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
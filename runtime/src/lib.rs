@@ -78,15 +78,12 @@ pub use sp_runtime::BuildStorage;
 
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::{CurrencyToVoteHandler, Author, TargetedFeeAdjustment};
+use impls::{CurrencyToVoteHandler, Author, TargetedFeeAdjustment, AdsPrices};
 
 /// Constant values used within the runtime.
 pub mod constants;
 use constants::{currency::*, time::*};
 
-// mod oracle;
-//mod prices;
-
 // Make the WASM binary available.
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -100,10 +97,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 253,
+	spec_version: 275,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// Native version.
@@ -709,18 +706,57 @@ impl did::Trait for Runtime {
 	type Event = Event;
 }
 
-// use oracle::sr25519::AuthorityId as OracleId;
-// We need to define the Transaction signer for that using the Key definition
-// type SubmitTransactionOracle = TransactionSubmitter<OracleId, Runtime, UncheckedExtrinsic>;
+parameter_types! {
+	pub const SwapVolumeWindow: BlockNumber = 1 * DAYS;
+	pub const MinSwapAmount: Balance = 1 * DOLLARS;
+}
+
+impl oracle::Trait for Runtime {
+	type AuthorityId = oracle::sr25519::AuthId;
+	type Event = Event;
+	type Call = Call;
+	type OnConflict = ();
+	type CouncilOrigin = EnsureRootOrHalfCouncil;
+	type Scheduler = Scheduler;
+	type VolumeWindow = SwapVolumeWindow;
+	type MinSwapAmount = MinSwapAmount;
+}
+
+impl prices::Trait for Runtime {
+	type AuthorityId = prices::crypto::AuthId;
+	type Event = Event;
+	type Call = Call;
+}
 
-// impl oracle::Trait for Runtime {
-// 	type AuthorityId = OracleId;
-// 	type Event = Event;
-// 	type Call = Call;
-// }
+parameter_types! {
+	pub const AdReportBond: Balance = 10 * DOLLARS;
+	pub const AdReportSlash: Balance = 50 * DOLLARS;
+	pub const AdEpochLength: BlockNumber = 1 * HOURS;
+	pub const AdRevealPeriod: BlockNumber = 10 * MINUTES;
+	pub const AdMinBid: Balance = 1 * CENTS;
+	pub const AdMaxBids: u32 = 100;
+	pub const AdAgentStake: Balance = 100 * DOLLARS;
+	pub const AdAgentUnbondPeriod: BlockNumber = 7 * DAYS;
+	pub const AdMaxAgentAds: u32 = 100;
+	pub const MaxAgentFee: Permill = Permill::from_percent(20);
+	pub const AdMaxPriceAge: Moment = 10 * 60 * 1000;
+}
 
 impl ads::Trait for Runtime {
 	type Event = Event;
+	type ModeratorOrigin = EnsureRootOrHalfCouncil;
+	type ReportBond = AdReportBond;
+	type ReportSlash = AdReportSlash;
+	type EpochLength = AdEpochLength;
+	type RevealPeriod = AdRevealPeriod;
+	type MinBid = AdMinBid;
+	type MaxBids = AdMaxBids;
+	type AgentStake = AdAgentStake;
+	type AgentUnbondPeriod = AdAgentUnbondPeriod;
+	type MaxAgentAds = AdMaxAgentAds;
+	type MaxAgentFee = MaxAgentFee;
+	type Prices = AdsPrices;
+	type MaxPriceAge = AdMaxPriceAge;
 }
 
 construct_runtime!(
@@ -758,8 +794,9 @@ construct_runtime!(
 		Scheduler: pallet_scheduler::{Module, Call, Storage, Event<T>},
 
 		Did: did::{Module, Storage, Call, Config<T>, Event<T>},
-		// Oracle: oracle::{Module, Storage, Call, Event<T>, ValidateUnsigned},
 		Ads: ads::{Module, Storage, Call, Config<T>, Event<T>},
+		Oracle: oracle::{Module, Storage, Call, Config<T>, Event<T>},
+		Prices: prices::{Module, Storage, Call, Config<T>, Event<T>},
 	}
 );
 
@@ -972,6 +1009,75 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl ads_runtime_api::AdsApi<Block, Hash, Balance> for Runtime {
+		fn owned_ads(owner: Hash) -> Vec<ads::AdIndex> {
+			Ads::owned_ads(owner)
+		}
+
+		fn active_ads(offset: u32, limit: u32) -> Vec<ads::AdIndex> {
+			Ads::active_ads(offset, limit)
+		}
+
+		fn active_ads_by_targeting(query: ads::TargetingQuery, offset: u32, limit: u32) -> Vec<ads::AdIndex> {
+			Ads::active_ads_by_targeting(query, offset, limit)
+		}
+
+		fn remaining_budget(adid: ads::AdIndex) -> Option<Balance> {
+			Ads::remaining_budget(adid)
+		}
+
+		fn rewards_paid(adid: ads::AdIndex) -> Balance {
+			Ads::rewards_paid(adid)
+		}
+
+		fn user_reward_count(user: Hash, adid: Option<ads::AdIndex>) -> u64 {
+			match adid {
+				Some(adid) => Ads::ad_user_rewards(adid, user) as u64,
+				None => Ads::user_reward_count(user),
+			}
+		}
+
+		fn campaign_stats(adid: ads::AdIndex) -> Option<ads::CampaignStats<Balance>> {
+			Ads::campaign_stats(adid)
+		}
+
+		fn slot_winners() -> Vec<(ads::AdIndex, Balance)> {
+			Ads::slot_winners()
+		}
+	}
+
+	impl did_runtime_api::DidApi<Block, Hash, Balance> for Runtime {
+		fn ads_share_count(did: Hash) -> u64 {
+			Did::ads_share_count(did)
+		}
+
+		fn ads_income(did: Hash) -> (Balance, Balance) {
+			Did::ads_income(did)
+		}
+	}
+
+	impl oracle_runtime_api::OracleSwapApi<Block, AccountId, Hash, Balance, BlockNumber> for Runtime {
+		fn swap(swap_id: Hash) -> Option<oracle::SwapInfo<AccountId, Hash, Balance, BlockNumber>> {
+			Oracle::swap(swap_id)
+		}
+
+		fn open_swaps(after: Option<Hash>, limit: u32) -> Vec<oracle::SwapInfo<AccountId, Hash, Balance, BlockNumber>> {
+			Oracle::open_swaps(after, limit)
+		}
+
+		fn swaps_by_receiver(did: Hash, offset: u32, limit: u32) -> Vec<oracle::SwapInfo<AccountId, Hash, Balance, BlockNumber>> {
+			Oracle::swaps_by_receiver(did, offset, limit)
+		}
+
+		fn reconciliation() -> oracle::Reconciliation<Balance> {
+			Oracle::reconciliation()
+		}
+
+		fn scan_job() -> Option<(oracle::EventLogSource, Option<oracle::ScanConfig>)> {
+			Oracle::scan_job()
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)